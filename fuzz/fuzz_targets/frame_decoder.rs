@@ -0,0 +1,85 @@
+//! Feeds arbitrary bytes, split into arbitrary chunks, to the USB frame
+//! decoder. It must never panic, must yield the same frames however the
+//! stream is split, and its counters must match what it yielded.
+#![no_main]
+
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use rsplayer_wire::{HostFrame, MAX_FRAME};
+
+#[path = "../../src/frame.rs"]
+mod frame;
+
+use frame::{FrameDecoder, FrameError, FrameStats};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    stream: Vec<u8>,
+    /// Chunk lengths, cycled, as 1 + n % 64: a USB packet is at most 64
+    /// bytes.
+    splits: Vec<u8>,
+}
+
+/// What each result was; `HostFrame` itself need not be comparable.
+fn kinds(
+    decoder: &mut FrameDecoder<HostFrame, MAX_FRAME>,
+    chunks: &mut dyn Iterator<Item = &[u8]>,
+    stats: &mut FrameStats,
+) -> Vec<Option<FrameError>> {
+    let mut out = Vec::new();
+    for chunk in chunks {
+        for res in decoder.feed(chunk) {
+            match res {
+                Ok(_) => stats.frames += 1,
+                Err(FrameError::Overflow) => stats.overflows += 1,
+                Err(FrameError::Decode) => stats.decode_errors += 1,
+            }
+            out.push(res.err());
+        }
+    }
+    out
+}
+
+fuzz_target!(|input: Input| {
+    let mut whole = FrameDecoder::<HostFrame, MAX_FRAME>::new();
+    let mut whole_stats = FrameStats::default();
+    let expected = kinds(
+        &mut whole,
+        &mut core::iter::once(input.stream.as_slice()),
+        &mut whole_stats,
+    );
+    assert_eq!(whole.stats(), whole_stats);
+
+    let mut split = FrameDecoder::<HostFrame, MAX_FRAME>::new();
+    let mut split_stats = FrameStats::default();
+    let mut lens = input.splits.iter().map(|&n| 1 + n as usize % 64).cycle();
+    let mut rest = input.stream.as_slice();
+    let mut chunks = core::iter::from_fn(|| {
+        if rest.is_empty() {
+            return None;
+        }
+        let n = lens.next().unwrap_or(rest.len()).min(rest.len());
+        let (chunk, tail) = rest.split_at(n);
+        rest = tail;
+        Some(chunk)
+    });
+    let got = kinds(&mut split, &mut chunks, &mut split_stats);
+    assert_eq!(split.stats(), split_stats);
+    assert_eq!(got, expected);
+
+    // Every result ends a frame or starts skipping one.
+    let delimiters = input.stream.iter().filter(|&&b| b == 0).count() as u32;
+    assert!(whole_stats.frames + whole_stats.decode_errors <= delimiters);
+
+    // After a reset the next frame decodes as on a fresh decoder.
+    split.reset();
+    let mut fresh = FrameDecoder::<HostFrame, MAX_FRAME>::new();
+    let after = input
+        .stream
+        .iter()
+        .position(|&b| b == 0)
+        .map_or(&[][..], |i| &input.stream[i..]);
+    let a: Vec<_> = split.feed(after).map(|r| r.err()).collect();
+    let b: Vec<_> = fresh.feed(after).map(|r| r.err()).collect();
+    assert_eq!(a, b);
+});
@@ -0,0 +1,25 @@
+//! Firmware modules that do not touch the hardware, compiled from `../src`
+//! at the same module paths so their `#[cfg(test)]` tests run on the host:
+//!
+//! ```sh
+//! cargo make test-host
+//! ```
+
+#[path = "../../src/dac"]
+pub mod dac {
+    pub mod sample_rate;
+}
+
+#[path = "../../src/frame.rs"]
+pub mod frame;
+
+#[path = "../../src/outbox.rs"]
+pub mod outbox;
+
+#[path = "../../src/rate_meter"]
+pub mod rate_meter {
+    pub mod classify;
+}
+
+#[path = "../../src/setting.rs"]
+pub mod setting;
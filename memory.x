@@ -1,6 +1,12 @@
 MEMORY {
+    /* The bootloader (bootloader/) owns BOOT2 and the first 24K; keep    */
+    /* these partitions in sync with bootloader/memory.x.                  */
     BOOT2 : ORIGIN = 0x10000000, LENGTH = 0x100
-    FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100
+    BOOTLOADER_STATE : ORIGIN = 0x10006000, LENGTH = 4K
+    FLASH : ORIGIN = 0x10007000, LENGTH = 480K
+    /* Staging area for updates, one page larger than FLASH for the swap. */
+    DFU   : ORIGIN = 0x1007F000, LENGTH = 484K
+    /* Settings sectors start at 0x10100000, see flash.rs.                */
 
     /* Pick one of the two options for RAM layout     */
 
@@ -15,3 +21,9 @@ MEMORY {
     /* SCRATCH_A: ORIGIN = 0x20040000, LENGTH = 4K    */
     /* SCRATCH_B: ORIGIN = 0x20041000, LENGTH = 4K    */
 }
+
+__bootloader_state_start = ORIGIN(BOOTLOADER_STATE) - ORIGIN(BOOT2);
+__bootloader_state_end = ORIGIN(BOOTLOADER_STATE) + LENGTH(BOOTLOADER_STATE) - ORIGIN(BOOT2);
+
+__bootloader_dfu_start = ORIGIN(DFU) - ORIGIN(BOOT2);
+__bootloader_dfu_end = ORIGIN(DFU) + LENGTH(DFU) - ORIGIN(BOOT2);
@@ -6,7 +6,7 @@ use embassy_sync::signal::Signal;
 use embassy_time::Timer;
 
 use crate::dac::common::SampleRate;
-use crate::{AmaneroPins, Command};
+use crate::{AmaneroPins, Command, RateSource};
 
 pub static REFRESH_SAMPLE_RATE: Signal<CriticalSectionRawMutex, ()> = Signal::new();
 
@@ -83,7 +83,7 @@ pub async fn listen_pin_changes(
     let initial_rate = amanero.read_sample_rate();
     if initial_rate != SampleRate::Unknown {
         debug!("amanero send initial rate command: {}", initial_rate);
-        control.send(Command::UpdateSampleRate(initial_rate)).await;
+        control.send(Command::UpdateSampleRate(RateSource::Amanero, initial_rate)).await;
     }
 
     loop {
@@ -104,7 +104,7 @@ pub async fn listen_pin_changes(
         {
             let sample_rate = amanero.read_sample_rate();
             debug!("amanero send update rate command: {}", sample_rate);
-            control.send(Command::UpdateSampleRate(sample_rate)).await;
+            control.send(Command::UpdateSampleRate(RateSource::Amanero, sample_rate)).await;
         }
     }
 }
@@ -0,0 +1,64 @@
+use defmt::debug;
+pub use rsplayer_wire::ArtFormat;
+
+/// Side of the square panel region reserved for the cover. Hosts scale the
+/// image to fit before sending it.
+pub const ART_MAX: u16 = 196;
+
+pub fn bytes_per_pixel(format: ArtFormat) -> usize {
+    match format {
+        ArtFormat::Rgb565 => 2,
+        ArtFormat::Rgb666 => 3,
+    }
+}
+
+/// Bookkeeping for the cover being received. The pixels themselves go
+/// straight to the panel chunk by chunk.
+pub struct ArtTransfer {
+    pub id: u16,
+    pub width: u16,
+    pub height: u16,
+    pub format: ArtFormat,
+    received: u32,
+}
+
+impl ArtTransfer {
+    /// `None` for an empty image or one larger than the art region.
+    pub fn begin(id: u16, width: u16, height: u16, format: ArtFormat) -> Option<Self> {
+        if width == 0 || height == 0 || width > ART_MAX || height > ART_MAX {
+            return None;
+        }
+        Some(ArtTransfer {
+            id,
+            width,
+            height,
+            format,
+            received: 0,
+        })
+    }
+
+    fn size(&self) -> u32 {
+        self.width as u32 * self.height as u32 * bytes_per_pixel(self.format) as u32
+    }
+
+    /// Takes a chunk of `len` bytes at byte `offset` and returns the index
+    /// of its first pixel. Chunks of another transfer, out of order or not
+    /// made of whole pixels are refused; the host starts over with a new id.
+    pub fn chunk(&mut self, id: u16, offset: u32, len: usize) -> Option<u32> {
+        let bpp = bytes_per_pixel(self.format) as u32;
+        if id != self.id
+            || offset != self.received
+            || len as u32 % bpp != 0
+            || offset + len as u32 > self.size()
+        {
+            debug!("Art chunk {}@{} refused", id, offset);
+            return None;
+        }
+        self.received += len as u32;
+        Some(offset / bpp)
+    }
+
+    pub fn done(&self) -> bool {
+        self.received == self.size()
+    }
+}
@@ -0,0 +1,112 @@
+use core::fmt::Write;
+use core::sync::atomic::AtomicBool;
+
+use heapless::{String, Vec};
+use rsplayer_wire::QUEUE_PAGE;
+
+use crate::library::LibraryBrowser;
+use crate::queue::QueueBrowser;
+
+/// Set while a browser is open; the rotary and IR tasks then send browse
+/// commands instead of volume and play/pause.
+pub static BROWSING: AtomicBool = AtomicBool::new(false);
+
+/// List screen over the header and main area.
+pub enum Browser {
+    Queue(QueueBrowser),
+    Library(LibraryBrowser),
+}
+
+/// Cursor over a host list that is sent one page at a time. Only the page
+/// around the cursor is held; moving off it asks the host for another.
+pub struct PagedList<T> {
+    total: u32,
+    offset: u32,
+    items: Vec<T, QUEUE_PAGE>,
+    /// `None` until the first page arrives.
+    cursor: Option<u32>,
+}
+
+impl<T> PagedList<T> {
+    pub fn new() -> Self {
+        PagedList {
+            total: 0,
+            offset: 0,
+            items: Vec::new(),
+            cursor: None,
+        }
+    }
+
+    /// Takes a page from the host. The first one puts the cursor on `start`,
+    /// or on the top of the page.
+    pub fn page(&mut self, offset: u32, total: u32, items: Vec<T, QUEUE_PAGE>, start: Option<u32>) {
+        self.offset = offset;
+        self.total = total;
+        self.items = items;
+        let cursor = self.cursor.unwrap_or(start.unwrap_or(offset));
+        self.cursor = Some(cursor.min(total.saturating_sub(1)));
+    }
+
+    /// Moves the cursor by `delta`, stopping at either end. Returns the
+    /// offset of the page to request when the cursor left the one held.
+    pub fn step(&mut self, delta: i32) -> Option<u32> {
+        let cursor = self.cursor?;
+        if self.total == 0 {
+            return None;
+        }
+        let cursor = cursor.saturating_add_signed(delta).min(self.total - 1);
+        self.cursor = Some(cursor);
+        let held = self.offset..self.offset + self.items.len() as u32;
+        if held.contains(&cursor) {
+            return None;
+        }
+        self.offset = cursor - cursor % QUEUE_PAGE as u32;
+        self.items.clear();
+        Some(self.offset)
+    }
+
+    /// Row of the held page showing list index `index`.
+    pub fn row(&self, index: Option<u32>) -> Option<usize> {
+        index
+            .and_then(|i| i.checked_sub(self.offset))
+            .map(|r| r as usize)
+            .filter(|&r| r < self.items.len())
+    }
+
+    /// Row under the cursor, once its page has arrived.
+    pub fn selected_row(&self) -> Option<usize> {
+        self.row(self.cursor)
+    }
+
+    /// List index under the cursor, once its page has arrived.
+    pub fn selected_index(&self) -> Option<u32> {
+        self.selected_row().map(|row| self.offset + row as u32)
+    }
+
+    pub fn selected(&self) -> Option<&T> {
+        self.selected_row().map(|row| &self.items[row])
+    }
+
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Heading for the list, e.g. "Queue 12/40".
+    pub fn title(&self, name: &str) -> String<24> {
+        let mut title = String::new();
+        match self.cursor {
+            Some(_) if self.total == 0 => write!(title, "{} empty", name).ok(),
+            Some(c) if self.selected_row().is_some() => {
+                write!(title, "{} {}/{}", name, c + 1, self.total).ok()
+            }
+            _ => title.push_str("Loading...").ok(),
+        };
+        title
+    }
+}
+
+impl<T> Default for PagedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,10 +1,17 @@
 pub mod common;
+pub mod sample_rate;
 
 #[cfg(feature = "ak4490")]
 pub mod ak4490;
 #[cfg(feature = "ak4497")]
 pub mod ak4497;
 
+/// Filter and sound setting ranges of the fitted chip.
+#[cfg(feature = "ak4490")]
+pub use crate::setting::ak4490::{FILTER, SOUND_SETTING};
+#[cfg(feature = "ak4497")]
+pub use crate::setting::ak4497::{FILTER, SOUND_SETTING};
+
 #[cfg(all(feature = "ak4490", feature = "ak4497"))]
 compile_error!("Features 'ak4490' and 'ak4497' are mutually exclusive.");
 
@@ -23,7 +23,7 @@ impl Akm44xxDac {
         self.change_sound_setting(self.sound_setting).await;
         for i in 0..9 {
             let register = self.i2c_helper.read_register(i).await;
-            info!("Register {:x} = {:b}", i, register)
+            debug!("Register {:x} = {:b}", i, register)
         }
     }
 
@@ -56,14 +56,12 @@ impl Akm44xxDac {
             }
         }
     }
-    pub async fn next_sound_setting(&mut self) -> u8 {
-        if self.sound_setting > 3 {
-            self.sound_setting = 0;
-        }
-        self.sound_setting += 1;
-        self.change_sound_setting(self.sound_setting).await;
+    /// Applies sound setting `val`, already resolved against
+    /// `dac::SOUND_SETTING`.
+    pub async fn set_sound_setting(&mut self, val: u8) {
+        self.sound_setting = val;
+        self.change_sound_setting(val).await;
         self.reset().await;
-        self.sound_setting
     }
 
     pub async fn filter(&mut self, typ: FilterType) {
@@ -95,14 +93,12 @@ impl Akm44xxDac {
             }
         };
     }
-    pub async fn next_filter(&mut self) -> u8 {
-        if self.filter_type > 4 {
-            self.filter_type = 0;
-        }
-        self.filter_type += 1;
-        self.filter(self.filter_type.into()).await;
+    /// Applies filter `val` (see `FilterType::from`), already resolved
+    /// against `dac::FILTER`.
+    pub async fn set_filter(&mut self, val: u8) {
+        self.filter_type = val;
+        self.filter(val.into()).await;
         self.reset().await;
-        self.filter_type
     }
     pub async fn dsd_pcm(&mut self, sample_rate: SampleRate) {
         if sample_rate.is_dsd() {
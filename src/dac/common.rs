@@ -1,10 +1,11 @@
-use crate::i2c_helper::I2CHelper;
-use crate::{DacResources, IrqsI2c};
+use crate::i2c_helper::{I2CHelper, I2cBus, ADDR_AK4497};
+use crate::DacResources;
 use embassy_rp::gpio::Level;
-use embassy_rp::i2c::Config;
-use embassy_rp::{gpio::Output, i2c};
+use embassy_rp::gpio::Output;
 use embassy_time::Timer;
 
+pub use super::sample_rate::SampleRate;
+
 pub struct Akm44xxDac {
     pub pdn_pin: Output<'static>,
     pub i2c_helper: I2CHelper,
@@ -12,16 +13,8 @@ pub struct Akm44xxDac {
     pub sound_setting: u8,
 }
 impl Akm44xxDac {
-    pub fn new(resources: DacResources) -> Self {
-        let cfg = Config::default();
-        let i2c = i2c::I2c::new_async(
-            resources.i2c,
-            resources.pin15_i2c_scl,
-            resources.pin14_i2c_sda,
-            IrqsI2c,
-            cfg,
-        );
-        let i2c_helper = I2CHelper::new(i2c).unwrap();
+    pub fn new(resources: DacResources, bus: &'static I2cBus) -> Self {
+        let i2c_helper = I2CHelper::new(bus, ADDR_AK4497);
         Self {
             pdn_pin: Output::new(resources.pin2_dac_pdn, Level::High),
             i2c_helper,
@@ -74,6 +67,12 @@ impl Akm44xxDac {
         .await;
     }
 
+    /// True while the DAC is not answering on I2C, e.g. a loose ribbon or
+    /// the DAC board's supply not coming up.
+    pub fn is_faulted(&self) -> bool {
+        self.i2c_helper.is_faulted()
+    }
+
     pub async fn reset(&mut self) {
         self.i2c_helper.change_bit(0, 0, false).await;
         Timer::after_millis(50).await;
@@ -81,65 +80,6 @@ impl Akm44xxDac {
     }
 }
 
-#[derive(Eq, PartialEq, PartialOrd, Clone, Copy, defmt::Format, Debug)]
-pub enum SampleRate {
-    Pcm32,
-    Pcm441,
-    Pcm48,
-    Pcm882,
-    Pcm96,
-    Pcm1764,
-    Pcm192,
-    Pcm3528,
-    Pcm384,
-    Pcm7056,
-    Pcm768,
-    Pcm14112,
-    Pcm1536,
-    Dsd64,
-    Dsd128,
-    Dsd256,
-    Dsd512,
-    Dsd1024,
-    Unknown,
-}
-
-impl SampleRate {
-    pub fn to_str(self) -> (&'static str, &'static str, &'static str) {
-        match self {
-            SampleRate::Pcm32 => ("PCM", "32 kHz", "32 bit"),
-            SampleRate::Pcm441 => ("PCM", "44.1 kHz", "32 bit"),
-            SampleRate::Pcm48 => ("PCM", "48 kHz", "32 bit"),
-            SampleRate::Pcm882 => ("PCM", "88.2 kHz", "32 bit"),
-            SampleRate::Pcm96 => ("PCM", "96 kHz", "32 bit"),
-            SampleRate::Pcm1764 => ("PCM", "176.4 kHz", "32 bit"),
-            SampleRate::Pcm192 => ("PCM", "192 kHz", "32 bit"),
-            SampleRate::Pcm3528 => ("PCM", "352.8 kHz", "32 bit"),
-            SampleRate::Pcm384 => ("PCM", "384 kHz", "32 bit"),
-            SampleRate::Pcm7056 => ("PCM", "705.6 kHz", "32 bit"),
-            SampleRate::Pcm768 => ("PCM", "768 kHz", "32 bit"),
-            SampleRate::Pcm14112 => ("PCM", "1411.2 kHz", "32 bit"),
-            SampleRate::Pcm1536 => ("PCM", "1536 kHz", "32 bit"),
-            SampleRate::Dsd64 => ("DSD", "DSD64", "1 bit"),
-            SampleRate::Dsd128 => ("DSD", "DSD128", "1 bit"),
-            SampleRate::Dsd256 => ("DSD", "DSD256", "1 bit"),
-            SampleRate::Dsd512 => ("DSD", "DSD512", "1 bit"),
-            SampleRate::Dsd1024 => ("DSD", "DSD1024", "1 bit"),
-            SampleRate::Unknown => ("", "", ""),
-        }
-    }
-    pub fn is_dsd(&self) -> bool {
-        matches!(
-            self,
-            SampleRate::Dsd1024
-                | SampleRate::Dsd512
-                | SampleRate::Dsd256
-                | SampleRate::Dsd128
-                | SampleRate::Dsd64
-        )
-    }
-}
-
 pub enum FilterType {
     Sharp,
     Slow,
@@ -0,0 +1,82 @@
+#[derive(Eq, PartialEq, PartialOrd, Clone, Copy, defmt::Format, Debug)]
+pub enum SampleRate {
+    Pcm32,
+    Pcm441,
+    Pcm48,
+    Pcm882,
+    Pcm96,
+    Pcm1764,
+    Pcm192,
+    Pcm3528,
+    Pcm384,
+    Pcm7056,
+    Pcm768,
+    Pcm14112,
+    Pcm1536,
+    Dsd64,
+    Dsd128,
+    Dsd256,
+    Dsd512,
+    Dsd1024,
+    Unknown,
+}
+
+impl SampleRate {
+    pub fn to_str(self) -> (&'static str, &'static str, &'static str) {
+        match self {
+            SampleRate::Pcm32 => ("PCM", "32 kHz", "32 bit"),
+            SampleRate::Pcm441 => ("PCM", "44.1 kHz", "32 bit"),
+            SampleRate::Pcm48 => ("PCM", "48 kHz", "32 bit"),
+            SampleRate::Pcm882 => ("PCM", "88.2 kHz", "32 bit"),
+            SampleRate::Pcm96 => ("PCM", "96 kHz", "32 bit"),
+            SampleRate::Pcm1764 => ("PCM", "176.4 kHz", "32 bit"),
+            SampleRate::Pcm192 => ("PCM", "192 kHz", "32 bit"),
+            SampleRate::Pcm3528 => ("PCM", "352.8 kHz", "32 bit"),
+            SampleRate::Pcm384 => ("PCM", "384 kHz", "32 bit"),
+            SampleRate::Pcm7056 => ("PCM", "705.6 kHz", "32 bit"),
+            SampleRate::Pcm768 => ("PCM", "768 kHz", "32 bit"),
+            SampleRate::Pcm14112 => ("PCM", "1411.2 kHz", "32 bit"),
+            SampleRate::Pcm1536 => ("PCM", "1536 kHz", "32 bit"),
+            SampleRate::Dsd64 => ("DSD", "DSD64", "1 bit"),
+            SampleRate::Dsd128 => ("DSD", "DSD128", "1 bit"),
+            SampleRate::Dsd256 => ("DSD", "DSD256", "1 bit"),
+            SampleRate::Dsd512 => ("DSD", "DSD512", "1 bit"),
+            SampleRate::Dsd1024 => ("DSD", "DSD1024", "1 bit"),
+            SampleRate::Unknown => ("", "", ""),
+        }
+    }
+    /// Nominal frame rate for PCM, bit clock for DSD, 0 when unknown.
+    pub fn hz(self) -> u32 {
+        match self {
+            SampleRate::Pcm32 => 32_000,
+            SampleRate::Pcm441 => 44_100,
+            SampleRate::Pcm48 => 48_000,
+            SampleRate::Pcm882 => 88_200,
+            SampleRate::Pcm96 => 96_000,
+            SampleRate::Pcm1764 => 176_400,
+            SampleRate::Pcm192 => 192_000,
+            SampleRate::Pcm3528 => 352_800,
+            SampleRate::Pcm384 => 384_000,
+            SampleRate::Pcm7056 => 705_600,
+            SampleRate::Pcm768 => 768_000,
+            SampleRate::Pcm14112 => 1_411_200,
+            SampleRate::Pcm1536 => 1_536_000,
+            SampleRate::Dsd64 => 2_822_400,
+            SampleRate::Dsd128 => 5_644_800,
+            SampleRate::Dsd256 => 11_289_600,
+            SampleRate::Dsd512 => 22_579_200,
+            SampleRate::Dsd1024 => 45_158_400,
+            SampleRate::Unknown => 0,
+        }
+    }
+    pub fn is_dsd(&self) -> bool {
+        matches!(
+            self,
+            SampleRate::Dsd1024
+                | SampleRate::Dsd512
+                | SampleRate::Dsd256
+                | SampleRate::Dsd128
+                | SampleRate::Dsd64
+        )
+    }
+}
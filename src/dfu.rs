@@ -0,0 +1,161 @@
+use embassy_boot_rp::{BlockingFirmwareUpdater, FirmwareUpdaterConfig, State};
+use embassy_embedded_hal::flash::partition::BlockingPartition;
+use embassy_rp::flash::{ERASE_SIZE, WRITE_SIZE};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use rsplayer_wire::DfuError;
+use static_cell::StaticCell;
+
+use crate::flash::{FlashDevice, SharedFlash};
+use crate::hostlog::host_log;
+
+/// Largest image that fits the active partition (FLASH in memory.x).
+const MAX_IMAGE: u32 = 480 * 1024;
+
+type Partition = BlockingPartition<'static, CriticalSectionRawMutex, FlashDevice>;
+
+/// Scratch for writes to the bootloader state partition.
+static ALIGNED: StaticCell<[u8; WRITE_SIZE]> = StaticCell::new();
+
+struct Session {
+    size: u32,
+    crc32: u32,
+    received: u32,
+    crc: u32,
+}
+
+/// Receives a new image into the DFU partition for the bootloader to swap
+/// in on the next reset. Data is staged one erase sector at a time, so a
+/// sector is erased right before it is written and an aborted transfer
+/// never touches more flash than it sent.
+pub struct Dfu {
+    updater: BlockingFirmwareUpdater<'static, Partition, Partition>,
+    sector: [u8; ERASE_SIZE],
+    session: Option<Session>,
+}
+
+impl Dfu {
+    pub fn new(flash: &'static SharedFlash) -> Self {
+        let config = FirmwareUpdaterConfig::from_linkerfile_blocking(flash, flash);
+        Dfu {
+            updater: BlockingFirmwareUpdater::new(config, ALIGNED.init([0; WRITE_SIZE])),
+            sector: [0xFF; ERASE_SIZE],
+            session: None,
+        }
+    }
+
+    pub fn begin(&mut self, size: u32, crc32: u32) -> Result<(), DfuError> {
+        if size == 0 {
+            return Err(DfuError::Empty);
+        }
+        if size > MAX_IMAGE {
+            return Err(DfuError::TooLarge);
+        }
+        host_log!(Info, "DFU: receiving {} byte image", size);
+        self.sector.fill(0xFF);
+        self.session = Some(Session {
+            size,
+            crc32,
+            received: 0,
+            crc: !0,
+        });
+        Ok(())
+    }
+
+    /// Takes the chunk at `offset` and returns the total received so far.
+    /// Chunks must arrive in order. A chunk repeating bytes already received
+    /// (host retry after a lost ack, possibly re-split) only has its new
+    /// tail written; nothing is written twice.
+    pub fn chunk(&mut self, offset: u32, data: &[u8]) -> Result<u32, DfuError> {
+        let session = self.session.as_mut().ok_or(DfuError::NoSession)?;
+        let Some(skip) = session.received.checked_sub(offset) else {
+            return Err(DfuError::BadOffset);
+        };
+        let data = data.get(skip as usize..).unwrap_or_default();
+        if data.is_empty() {
+            return Ok(session.received);
+        }
+        if session.received + data.len() as u32 > session.size {
+            return Err(DfuError::TooLarge);
+        }
+        session.crc = crc32_update(session.crc, data);
+        let mut data = data;
+        while !data.is_empty() {
+            let pos = session.received as usize % ERASE_SIZE;
+            let n = data.len().min(ERASE_SIZE - pos);
+            self.sector[pos..pos + n].copy_from_slice(&data[..n]);
+            session.received += n as u32;
+            data = &data[n..];
+            if pos + n == ERASE_SIZE {
+                let start = session.received as usize - ERASE_SIZE;
+                if let Err(e) = Self::flush(&mut self.updater, &mut self.sector, start) {
+                    self.session = None;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(session.received)
+    }
+
+    /// Writes the tail, checks size and CRC and marks the image for the
+    /// bootloader. The caller resets the device afterwards.
+    pub fn finish(&mut self) -> Result<(), DfuError> {
+        let session = self.session.take().ok_or(DfuError::NoSession)?;
+        if session.received != session.size {
+            return Err(DfuError::BadOffset);
+        }
+        if !session.crc != session.crc32 {
+            host_log!(Warn, "DFU: CRC mismatch, image discarded");
+            return Err(DfuError::Crc);
+        }
+        let tail = session.received as usize % ERASE_SIZE;
+        if tail != 0 {
+            Self::flush(
+                &mut self.updater,
+                &mut self.sector,
+                session.received as usize - tail,
+            )?;
+        }
+        self.updater.mark_updated().map_err(|_| DfuError::Flash)?;
+        host_log!(Info, "DFU: image verified, swapping on reset");
+        Ok(())
+    }
+
+    pub fn abort(&mut self) {
+        if self.session.take().is_some() {
+            host_log!(Info, "DFU: transfer aborted");
+        }
+    }
+
+    /// Called once the firmware has proven itself after an update; without
+    /// it the bootloader reverts to the previous image on the next reset.
+    pub fn confirm(&mut self) {
+        if let Ok(State::Swap) = self.updater.get_state() {
+            match self.updater.mark_booted() {
+                Ok(()) => host_log!(Info, "DFU: new firmware confirmed"),
+                Err(_) => host_log!(Warn, "DFU: failed to confirm new firmware"),
+            }
+        }
+    }
+
+    fn flush(
+        updater: &mut BlockingFirmwareUpdater<'static, Partition, Partition>,
+        sector: &mut [u8; ERASE_SIZE],
+        offset: usize,
+    ) -> Result<(), DfuError> {
+        let res = updater.write_firmware(offset, sector);
+        sector.fill(0xFF);
+        res.map_err(|_| DfuError::Flash)
+    }
+}
+
+/// CRC-32 (IEEE 802.3), the one `zlib`/`crc32` tools print. Bitwise rather
+/// than table driven; the transfer rate is bounded by USB round trips anyway.
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    crc
+}
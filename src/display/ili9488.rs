@@ -1,6 +1,7 @@
 #![allow(unused_imports)]
 
 use core::fmt::Write;
+use embassy_time::{Duration, Instant};
 use embedded_graphics::{
     pixelcolor::Rgb666,
     prelude::*,
@@ -8,14 +9,18 @@ use embedded_graphics::{
     text::{renderer::TextRenderer, Alignment, Baseline, Text, TextStyleBuilder},
     Drawable,
 };
-use heapless::String;
+use heapless::{Deque, String, Vec};
 use u8g2_fonts::{
     fonts,
     types::{FontColor, HorizontalAlignment, VerticalPosition},
     FontRenderer, U8g2TextStyle,
 };
 
+use crate::art::{self, ArtFormat};
 use crate::PlaybackMode;
+use rsplayer_wire::{
+    Severity, TrackExt, NOTIFY_LEN, QUEUE_LABEL_LEN, QUEUE_PAGE, SPECTRUM_MAX_BANDS,
+};
 
 // UI Constants (Merged from ui/src/lib.rs)
 pub const COL_BG_BASE: Rgb666 = Rgb666::BLACK;
@@ -34,6 +39,63 @@ const VU_WIDTH: u32 = 10;
 const VU_MAX_HEIGHT: u32 = 202;
 const VU_TOP_Y: i32 = 62;
 
+// ArtInfo layout: cover on the left of the main area, track info beside it.
+const ART_X: i32 = 8;
+const ART_Y: i32 = 64;
+const ART_TEXT_X: i32 = ART_X + art::ART_MAX as i32 + 8;
+
+// Browse lists: a title line, then rows down to the footer rule.
+const LIST_ROWS: usize = 6;
+const LIST_ROW_Y: i32 = 40;
+const LIST_ROW_HEIGHT: i32 = 37;
+
+// Notifications cover the footer band until they expire.
+const TOAST_QUEUE: usize = 4;
+const TOAST_DEFAULT_MS: u16 = 3000;
+
+// Spectrum mode: band bars across the main area, each with a peak cap that
+// sinks back by SPECTRUM_PEAK_FALL px per frame.
+const SPECTRUM_X: i32 = 12;
+const SPECTRUM_WIDTH: u32 = 456;
+const SPECTRUM_MAX_HEIGHT: u32 = 190;
+const SPECTRUM_BOTTOM_Y: i32 = 260;
+const SPECTRUM_GAP: u32 = 2;
+const SPECTRUM_CAP: u32 = 3;
+const SPECTRUM_PEAK_FALL: u32 = 4;
+
+/// Appends one part of the track details line, separated from the previous.
+fn push_detail(line: &mut String<96>, part: core::fmt::Arguments) {
+    if !line.is_empty() {
+        line.push_str(" | ").ok();
+    }
+    line.write_fmt(part).ok();
+}
+
+/// "mm:ss", or "h:mm:ss" from an hour on.
+fn format_ms(ms: u32) -> String<16> {
+    let secs = ms / 1000;
+    let mut s = String::new();
+    if secs >= 3600 {
+        write!(s, "{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60).ok();
+    } else {
+        write!(s, "{:02}:{:02}", secs / 60, secs % 60).ok();
+    }
+    s
+}
+
+/// One cover pixel in the wire format: RGB565 big-endian, or RGB666 as
+/// three bytes with each channel in the upper six bits.
+fn art_color(format: ArtFormat, px: &[u8]) -> Rgb666 {
+    match format {
+        ArtFormat::Rgb565 => {
+            let v = u16::from_be_bytes([px[0], px[1]]);
+            let (r, g, b) = ((v >> 11) as u8, (v >> 5) as u8 & 0x3F, v as u8 & 0x1F);
+            Rgb666::new(r << 1 | r >> 4, g, b << 1 | b >> 4)
+        }
+        ArtFormat::Rgb666 => Rgb666::new(px[0] >> 2, px[1] >> 2, px[2] >> 2),
+    }
+}
+
 // Shared buffer for line drawing to save RAM. Sized for the tallest region
 // drawn in one blit: a BigInfo scrolling text strip (480×70) — scroll strips
 // must blit atomically or the panel shows sheared text mid-update.
@@ -87,6 +149,10 @@ pub enum DisplayMode {
     Normal = 0,
     VuMeter = 1,
     BigInfo = 2,
+    /// Album art sent by the host next to the track info.
+    ArtInfo = 3,
+    /// Band magnitudes sent by the host, drawn as bars with peak caps.
+    Spectrum = 4,
 }
 
 impl From<u8> for DisplayMode {
@@ -95,16 +161,62 @@ impl From<u8> for DisplayMode {
             0 => DisplayMode::Normal,
             1 => DisplayMode::VuMeter,
             2 => DisplayMode::BigInfo,
+            3 => DisplayMode::ArtInfo,
+            4 => DisplayMode::Spectrum,
             _ => DisplayMode::Normal,
         }
     }
 }
 
+/// Page of a browse list (play queue, library) shown over the header and
+/// main area.
+struct ListView {
+    title: String<24>,
+    rows: Vec<String<QUEUE_LABEL_LEN>, QUEUE_PAGE>,
+    selected: Option<usize>,
+    playing: Option<usize>,
+}
+
+/// Playback position as last reported by the host, advanced locally while
+/// playing.
+struct PlaybackClock {
+    position_ms: u32,
+    duration_ms: u32,
+    playing: bool,
+    at: Instant,
+    /// Second and bar width last drawn; `None` forces a redraw.
+    drawn: Option<(u32, u32)>,
+}
+
+impl PlaybackClock {
+    fn position_ms(&self) -> u32 {
+        let mut pos = self.position_ms;
+        if self.playing {
+            pos = pos.saturating_add(self.at.elapsed().as_millis() as u32);
+        }
+        if self.duration_ms > 0 {
+            pos.min(self.duration_ms)
+        } else {
+            pos
+        }
+    }
+}
+
+/// Host notification waiting for, or taking, the footer band.
+struct Toast {
+    text: String<NOTIFY_LEN>,
+    severity: Severity,
+    duration: Duration,
+}
+
 pub struct PlayerDisplay<D> {
     pub display: D,
     track_artist: String<64>,
     track_title: String<64>,
     track_album: String<64>,
+    /// Track number, year, genre, codec — one line built from `TrackExt`.
+    track_details: String<96>,
+    up_next: String<136>,
     scroll_tick: i32,
     scroll_accumulator: i32,
     last_total_time: String<16>,
@@ -113,12 +225,25 @@ pub struct PlayerDisplay<D> {
     footer_format: String<16>,
     footer_freq: String<16>,
     footer_bit_depth: String<16>,
+    /// Host stopped sending heartbeats; shown in the last footer section.
+    host_offline: bool,
+    /// Browse list, while open; main area drawing is held off under it.
+    list: Option<ListView>,
+    /// Notification shown over the footer and when it expires.
+    toast: Option<(Toast, Instant)>,
+    /// Notifications waiting their turn.
+    toasts: Deque<Toast, TOAST_QUEUE>,
+    /// Drives the progress bar between host position updates.
+    clock: Option<PlaybackClock>,
     force_redraw: bool,
     /// Last drawn side VU bar heights (px). `None` forces a full bar
     /// repaint; otherwise only the span between old and new level is drawn.
     last_vu_side: Option<(u32, u32)>,
     /// Last drawn fullscreen VU bar widths (px), same delta scheme.
     last_vu_full: Option<(u32, u32)>,
+    /// Last drawn spectrum bar height and peak cap position (px) per band.
+    /// Empty, or a different band count, forces a full repaint.
+    last_spectrum: Vec<(u32, u32), SPECTRUM_MAX_BANDS>,
 }
 
 impl<D> PlayerDisplay<D>
@@ -132,6 +257,8 @@ where
             track_title: String::new(),
             track_artist: String::new(),
             track_album: String::new(),
+            track_details: String::new(),
+            up_next: String::new(),
             scroll_tick: 0,
             scroll_accumulator: 0,
             last_total_time: String::new(),
@@ -140,13 +267,22 @@ where
             footer_format: String::new(),
             footer_freq: String::new(),
             footer_bit_depth: String::new(),
+            host_offline: false,
+            list: None,
+            toast: None,
+            toasts: Deque::new(),
+            clock: None,
             force_redraw: false,
             last_vu_side: None,
             last_vu_full: None,
+            last_spectrum: Vec::new(),
         }
     }
 
     pub async fn tick(&mut self) {
+        self.update_toast();
+        self.update_clock();
+
         self.scroll_accumulator += 4; // Move 4 pixels per tick (at 50ms = 80px/sec)
         const SCROLL_THRESHOLD: i32 = 4;
 
@@ -155,10 +291,14 @@ where
             self.scroll_accumulator = 0;
 
             let update_scrolling_only = !self.force_redraw;
-            if self.display_mode == DisplayMode::Normal {
+            if self.list.is_some() {
+                self.draw_list_internal(update_scrolling_only).await;
+            } else if self.display_mode == DisplayMode::Normal {
                 self.draw_track_info_internal(update_scrolling_only).await;
             } else if self.display_mode == DisplayMode::BigInfo {
                 self.draw_big_info_internal(update_scrolling_only).await;
+            } else if self.display_mode == DisplayMode::ArtInfo {
+                self.draw_art_info_internal(update_scrolling_only).await;
             }
             self.force_redraw = false;
         }
@@ -173,6 +313,7 @@ where
     pub fn set_display_mode(&mut self, mode: DisplayMode) {
         self.display_mode = mode;
         self.invalidate_vu();
+        self.invalidate_clock();
     }
 
     /// Forget the last drawn VU levels — call after painting over a meter
@@ -180,10 +321,12 @@ where
     fn invalidate_vu(&mut self) {
         self.last_vu_side = None;
         self.last_vu_full = None;
+        self.last_spectrum.clear();
     }
 
     pub fn draw_background(&mut self) {
         self.invalidate_vu();
+        self.invalidate_clock();
         Rectangle::new(Point::new(0, 0), self.display.size())
             .into_styled(embedded_graphics::primitives::PrimitiveStyle::with_fill(
                 COL_BG_BASE,
@@ -210,7 +353,7 @@ where
     }
 
     pub fn draw_header_status(&mut self, input: &str, filter: &str) {
-        if self.display_mode == DisplayMode::BigInfo {
+        if self.display_mode == DisplayMode::BigInfo || self.list.is_some() {
             return;
         }
         Rectangle::new(Point::new(5, 25), Size::new(340, 30))
@@ -230,10 +373,10 @@ where
             .draw(&mut self.display)
             .ok();
 
-        Text::new("| FLT:", Point::new(105, 45), style_label)
+        Text::new("| FLT:", Point::new(125, 45), style_label)
             .draw(&mut self.display)
             .ok();
-        Text::new(filter, Point::new(170, 45), style_value)
+        Text::new(filter, Point::new(190, 45), style_value)
             .draw(&mut self.display)
             .ok();
     }
@@ -258,7 +401,110 @@ where
         self.draw_footer_internal();
     }
 
+    pub fn draw_host_status(&mut self, online: bool) {
+        self.host_offline = !online;
+        self.draw_host_section();
+    }
+
+    /// Queues a notification; it is shown over the footer on the next tick
+    /// once those before it have expired. When the queue is full the oldest
+    /// waiting one is dropped. A `duration_ms` of 0 means the default.
+    pub fn notify(&mut self, text: &str, severity: Severity, duration_ms: u16) {
+        let duration_ms = if duration_ms == 0 {
+            TOAST_DEFAULT_MS
+        } else {
+            duration_ms
+        };
+        let toast = Toast {
+            text: text.try_into().unwrap_or_default(),
+            severity,
+            duration: Duration::from_millis(duration_ms as u64),
+        };
+        if self.toasts.is_full() {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(toast).ok();
+    }
+
+    /// Takes down an expired notification, shows the next one, and puts
+    /// the footer back once none are left.
+    fn update_toast(&mut self) {
+        let now = Instant::now();
+        if self.toast.as_ref().is_some_and(|(_, until)| now < *until) {
+            return;
+        }
+        match self.toasts.pop_front() {
+            Some(next) => {
+                let until = now + next.duration;
+                self.toast = Some((next, until));
+                self.draw_toast();
+            }
+            None => {
+                if self.toast.take().is_some() {
+                    self.draw_footer_internal();
+                }
+            }
+        }
+    }
+
+    fn draw_toast(&mut self) {
+        let Some((toast, _)) = self.toast.as_ref() else {
+            return;
+        };
+        let width = 480;
+        let height = 49;
+        let color = match toast.severity {
+            Severity::Info => COL_1,
+            Severity::Warning => COL_TEXT,
+            Severity::Error => COL_VU_MAX,
+        };
+
+        let buffer_slice = unsafe { &mut LINE_BUFFER[..(width * height) as usize] };
+        buffer_slice.fill(COL_BG_BASE);
+        let mut target = LineBuffer::new(buffer_slice, width, height);
+
+        Rectangle::new(Point::zero(), Size::new(width, height))
+            .into_styled(embedded_graphics::primitives::PrimitiveStyle::with_stroke(
+                color, 2,
+            ))
+            .draw(&mut target)
+            .ok();
+
+        // Long messages drop to a smaller font rather than scrolling; they
+        // are only up for a few seconds.
+        let mut style = U8g2TextStyle::new(fonts::u8g2_font_helvB18_tf, color);
+        let text_width = style
+            .measure_string(&toast.text, Point::zero(), Baseline::Middle)
+            .bounding_box
+            .size
+            .width;
+        if text_width > width - 16 {
+            style = U8g2TextStyle::new(fonts::u8g2_font_helvB12_tf, color);
+        }
+        Text::with_text_style(
+            &toast.text,
+            Point::new(width as i32 / 2, height as i32 / 2),
+            style,
+            TextStyleBuilder::new()
+                .alignment(Alignment::Center)
+                .baseline(Baseline::Middle)
+                .build(),
+        )
+        .draw(&mut target)
+        .ok();
+
+        self.display
+            .fill_contiguous(
+                &Rectangle::new(Point::new(0, 271), Size::new(width, height)),
+                target.buffer.iter().cloned(),
+            )
+            .ok();
+    }
+
     fn draw_playback_mode_section(&mut self) {
+        if self.toast.is_some() {
+            return;
+        }
         let section_width = 480 / 4;
         let height = 49;
         let x = 0;
@@ -306,6 +552,9 @@ where
             .ok();
     }
     fn draw_footer_text_section(&mut self, section_idx: i32, text: &str) {
+        if self.toast.is_some() {
+            return;
+        }
         let section_width = 480 / 4;
         let height = 49;
         let x = section_idx * section_width;
@@ -340,20 +589,70 @@ where
     }
 
     fn draw_footer_internal(&mut self) {
+        if self.toast.is_some() {
+            self.draw_toast();
+            return;
+        }
         let f = self.footer_format.clone();
         let fr = self.footer_freq.clone();
 
         self.draw_playback_mode_section();
         self.draw_footer_text_section(1, &f);
         self.draw_footer_text_section(2, &fr);
+        self.draw_host_section();
+    }
+
+    fn draw_host_section(&mut self) {
+        if self.toast.is_some() {
+            return;
+        }
+        let section_width = 480 / 4;
+        let height = 49;
+        let x = 3 * section_width;
+        let y = 271;
+
+        let buffer_slice =
+            unsafe { &mut LINE_BUFFER[..(section_width as usize * height as usize)] };
+        buffer_slice.fill(COL_BG_BASE);
+
+        let mut target = LineBuffer::new(buffer_slice, section_width as u32, height);
+
+        if self.host_offline {
+            let style = U8g2TextStyle::new(fonts::u8g2_font_helvB18_tf, COL_VU_MAX);
+            let text_style = TextStyleBuilder::new()
+                .alignment(Alignment::Center)
+                .baseline(Baseline::Middle)
+                .build();
+            for (text, line_y) in [("HOST", 13), ("OFFLINE", 36)] {
+                Text::with_text_style(
+                    text,
+                    Point::new(section_width / 2, line_y),
+                    style.clone(),
+                    text_style,
+                )
+                .draw(&mut target)
+                .ok();
+            }
+        }
+
+        self.display
+            .fill_contiguous(
+                &Rectangle::new(Point::new(x, y), Size::new(section_width as u32, height)),
+                target.buffer.iter().cloned(),
+            )
+            .ok();
     }
 
     pub fn draw_volume(&mut self, vol: u8) {
+        if self.list.is_some() {
+            return;
+        }
         if self.display_mode == DisplayMode::BigInfo {
+            // Below the details and up-next lines, clear of the footer rule.
             let width = 300;
-            let height = 60;
+            let height = 44;
             let screen_x = (480 - width as i32) / 2;
-            let screen_y = 200;
+            let screen_y = 220;
 
             let buffer_slice = unsafe { &mut LINE_BUFFER[..(width * height) as usize] };
             buffer_slice.fill(COL_BG_BASE);
@@ -368,7 +667,7 @@ where
 
             Text::with_text_style(
                 &vol_str,
-                Point::new(width as i32 / 2, height as i32 / 2 + 10),
+                Point::new(width as i32 / 2, height as i32 / 2 + 2),
                 style,
                 TextStyleBuilder::new()
                     .alignment(Alignment::Center)
@@ -426,6 +725,18 @@ where
 
 
     pub fn draw_track_info(&mut self, title: &str, artist: &str, album: &str) {
+        // Extended fields belong to the previous track once it changes;
+        // hosts that send them follow up with the new ones.
+        if (title, artist, album)
+            != (
+                self.track_title.as_str(),
+                self.track_artist.as_str(),
+                self.track_album.as_str(),
+            )
+        {
+            self.track_details.clear();
+            self.up_next.clear();
+        }
         self.track_title.clear();
         self.track_title.push_str(title).ok();
         self.track_artist.clear();
@@ -436,6 +747,43 @@ where
         self.force_redraw = true;
     }
 
+    pub fn draw_track_ext(&mut self, ext: &TrackExt) {
+        let d = &mut self.track_details;
+        d.clear();
+        match (ext.track_no, ext.track_total) {
+            (Some(n), Some(t)) => push_detail(d, format_args!("Track {}/{}", n, t)),
+            (Some(n), None) => push_detail(d, format_args!("Track {}", n)),
+            _ => {}
+        }
+        if let Some(year) = ext.year {
+            push_detail(d, format_args!("{}", year));
+        }
+        if let Some(genre) = &ext.genre {
+            push_detail(d, format_args!("{}", genre));
+        }
+        match (&ext.codec, ext.bit_depth) {
+            (Some(c), Some(b)) => push_detail(d, format_args!("{} {}-bit", c, b)),
+            (Some(c), None) => push_detail(d, format_args!("{}", c)),
+            (None, Some(b)) => push_detail(d, format_args!("{}-bit", b)),
+            (None, None) => {}
+        }
+        self.reset_scroll();
+        self.force_redraw = true;
+    }
+
+    /// Next queued track; an empty title clears the line.
+    pub fn draw_up_next(&mut self, title: &str, artist: &str) {
+        self.up_next.clear();
+        if !title.is_empty() {
+            write!(self.up_next, "Next: {}", title).ok();
+            if !artist.is_empty() {
+                write!(self.up_next, " - {}", artist).ok();
+            }
+        }
+        self.reset_scroll();
+        self.force_redraw = true;
+    }
+
     pub fn redraw_track_info(&mut self) {
         self.reset_scroll();
         self.force_redraw = true;
@@ -445,10 +793,68 @@ where
         self.track_title.clear();
         self.track_artist.clear();
         self.track_album.clear();
+        self.track_details.clear();
+        self.up_next.clear();
+        self.reset_scroll();
+        self.force_redraw = true;
+    }
+
+    /// Covers the header and main area with a browse list, showing
+    /// "Loading..." until the first page is drawn. The caller repaints the
+    /// screen after [`close_list`](Self::close_list).
+    pub fn open_list(&mut self) {
+        self.invalidate_vu();
+        let mut title = String::new();
+        title.push_str("Loading...").ok();
+        self.list = Some(ListView {
+            title,
+            rows: Vec::new(),
+            selected: None,
+            playing: None,
+        });
+        Rectangle::new(Point::new(0, 0), Size::new(480, 263))
+            .into_styled(embedded_graphics::primitives::PrimitiveStyle::with_fill(
+                COL_BG_BASE,
+            ))
+            .draw(&mut self.display)
+            .ok();
         self.reset_scroll();
         self.force_redraw = true;
     }
 
+    /// `selected` and `playing` index into `rows`.
+    pub fn draw_list<'a>(
+        &mut self,
+        title: &str,
+        rows: impl IntoIterator<Item = &'a str>,
+        selected: Option<usize>,
+        playing: Option<usize>,
+    ) {
+        let Some(view) = self.list.as_mut() else {
+            return;
+        };
+        // A newly selected row starts scrolling from the beginning.
+        let moved = view.selected != selected;
+        view.title.clear();
+        view.title.push_str(title).ok();
+        view.rows.clear();
+        for row in rows {
+            if view.rows.push(row.try_into().unwrap_or_default()).is_err() {
+                break;
+            }
+        }
+        view.selected = selected;
+        view.playing = playing;
+        if moved {
+            self.reset_scroll();
+        }
+        self.force_redraw = true;
+    }
+
+    pub fn close_list(&mut self) {
+        self.list = None;
+    }
+
     async fn draw_scrolling_text_line(
         &mut self,
         style: U8g2TextStyle<Rgb666>,
@@ -528,10 +934,11 @@ where
             .fill_contiguous(
                 &Rectangle::new(
                     Point::new(
-                        if self.display_mode == DisplayMode::Normal {
-                            VU_MARGIN_X
-                        } else {
-                            0
+                        match self.display_mode {
+                            _ if self.list.is_some() => 0,
+                            DisplayMode::Normal => VU_MARGIN_X,
+                            DisplayMode::ArtInfo => ART_TEXT_X,
+                            _ => 0,
                         },
                         y - (height as i32 / 2),
                     ),
@@ -557,7 +964,7 @@ where
         self.draw_scrolling_text_line(
             style_artist,
             &self.track_artist.clone(),
-            55,
+            45,
             70,
             content_width as u32,
             center_x,
@@ -567,13 +974,49 @@ where
         self.draw_scrolling_text_line(
             style_title,
             &self.track_title.clone(),
-            135,
+            120,
             70,
             content_width as u32,
             center_x,
             update_scrolling_only,
         )
         .await;
+        self.draw_extra_info_lines(172, content_width as u32, update_scrolling_only)
+            .await;
+    }
+
+    /// Track details and the up-next line, 30 px each starting at `y`
+    /// (center of the first). Shared by the BigInfo and ArtInfo layouts.
+    async fn draw_extra_info_lines(
+        &mut self,
+        y: i32,
+        content_width: u32,
+        update_scrolling_only: bool,
+    ) {
+        let style_details = U8g2TextStyle::new(fonts::u8g2_font_helvB18_tf, COL_TEXT);
+        let style_next = U8g2TextStyle::new(fonts::u8g2_font_helvB18_tf, Rgb666::WHITE);
+        let center_x = content_width as i32 / 2;
+
+        self.draw_scrolling_text_line(
+            style_details,
+            &self.track_details.clone(),
+            y,
+            30,
+            content_width,
+            center_x,
+            update_scrolling_only,
+        )
+        .await;
+        self.draw_scrolling_text_line(
+            style_next,
+            &self.up_next.clone(),
+            y + 31,
+            30,
+            content_width,
+            center_x,
+            update_scrolling_only,
+        )
+        .await;
     }
 
     async fn draw_track_info_internal(&mut self, update_scrolling_only: bool) {
@@ -616,8 +1059,162 @@ where
         .await;
     }
 
+    async fn draw_art_info_internal(&mut self, update_scrolling_only: bool) {
+        let style_song = U8g2TextStyle::new(fonts::u8g2_font_helvB24_tf, Rgb666::WHITE);
+        let style_artist = U8g2TextStyle::new(fonts::u8g2_font_helvB18_tf, COL_1);
+        let style_album = U8g2TextStyle::new(fonts::u8g2_font_helvB18_tf, COL_TEXT);
+
+        let content_width = 480 - ART_TEXT_X;
+        let center_x = content_width / 2;
+
+        self.draw_scrolling_text_line(
+            style_song,
+            &self.track_title.clone(),
+            95,
+            50,
+            content_width as u32,
+            center_x,
+            update_scrolling_only,
+        )
+        .await;
+        self.draw_scrolling_text_line(
+            style_artist,
+            &self.track_artist.clone(),
+            140,
+            35,
+            content_width as u32,
+            center_x,
+            update_scrolling_only,
+        )
+        .await;
+        self.draw_scrolling_text_line(
+            style_album,
+            &self.track_album.clone(),
+            180,
+            40,
+            content_width as u32,
+            center_x,
+            update_scrolling_only,
+        )
+        .await;
+        self.draw_extra_info_lines(218, content_width as u32, update_scrolling_only)
+            .await;
+    }
+
+    /// Title line, then the rows of the page around the selected one. Only
+    /// the selected row scrolls; the others stay at their start.
+    async fn draw_list_internal(&mut self, update_scrolling_only: bool) {
+        let Some((title, selected, playing)) = self
+            .list
+            .as_ref()
+            .map(|l| (l.title.clone(), l.selected, l.playing))
+        else {
+            return;
+        };
+        if !update_scrolling_only {
+            let style_title = U8g2TextStyle::new(fonts::u8g2_font_helvB24_tf, Rgb666::WHITE);
+            self.draw_scrolling_text_line(style_title, &title, 20, 40, 480, 240, false)
+                .await;
+        }
+
+        let first = selected.map_or(0, |s| s - s % LIST_ROWS);
+        for row in 0..LIST_ROWS {
+            let idx = first + row;
+            let is_selected = selected == Some(idx);
+            if update_scrolling_only && !is_selected {
+                continue;
+            }
+            let style = if is_selected {
+                U8g2TextStyle::new(fonts::u8g2_font_helvB24_tf, COL_TEXT)
+            } else if playing == Some(idx) {
+                U8g2TextStyle::new(fonts::u8g2_font_helvB18_tf, Rgb666::WHITE)
+            } else {
+                U8g2TextStyle::new(fonts::u8g2_font_helvB18_tf, COL_1)
+            };
+            let text = self
+                .list
+                .as_ref()
+                .and_then(|l| l.rows.get(idx))
+                .cloned()
+                .unwrap_or_default();
+            self.draw_scrolling_text_line(
+                style,
+                &text,
+                LIST_ROW_Y + row as i32 * LIST_ROW_HEIGHT + LIST_ROW_HEIGHT / 2,
+                LIST_ROW_HEIGHT as u32,
+                480,
+                240,
+                update_scrolling_only,
+            )
+            .await;
+        }
+    }
+
+    /// Streams pixels `first_px..` of a `width`×`height` cover straight to
+    /// the panel, centered in the art region. Each run of pixels becomes
+    /// one window: the rest of a started row, then whole rows, so nothing
+    /// larger than a chunk is ever held in RAM.
+    pub fn draw_art(
+        &mut self,
+        width: u16,
+        height: u16,
+        format: ArtFormat,
+        first_px: u32,
+        data: &[u8],
+    ) {
+        if self.display_mode != DisplayMode::ArtInfo || self.list.is_some() {
+            return;
+        }
+        let bpp = art::bytes_per_pixel(format);
+        let w = width as u32;
+        let origin = Point::new(
+            ART_X + (art::ART_MAX - width) as i32 / 2,
+            ART_Y + (art::ART_MAX - height) as i32 / 2,
+        );
+        let mut px = first_px;
+        let mut data = data;
+        while data.len() >= bpp {
+            let remaining = (data.len() / bpp) as u32;
+            let (col, row) = (px % w, px / w);
+            let size = if col != 0 || remaining < w {
+                Size::new((w - col).min(remaining), 1)
+            } else {
+                Size::new(w, remaining / w)
+            };
+            let n = size.width * size.height;
+            let (run, rest) = data.split_at(n as usize * bpp);
+            self.display
+                .fill_contiguous(
+                    &Rectangle::new(origin + Point::new(col as i32, row as i32), size),
+                    run.chunks_exact(bpp).map(|p| art_color(format, p)),
+                )
+                .ok();
+            px += n;
+            data = rest;
+        }
+    }
+
+    pub fn clear_art(&mut self) {
+        if self.display_mode != DisplayMode::ArtInfo || self.list.is_some() {
+            return;
+        }
+        Rectangle::new(
+            Point::new(ART_X, ART_Y),
+            Size::new(art::ART_MAX as u32, art::ART_MAX as u32),
+        )
+        .into_styled(embedded_graphics::primitives::PrimitiveStyle::with_fill(
+            COL_BG_BASE,
+        ))
+        .draw(&mut self.display)
+        .ok();
+    }
+
     pub fn draw_vu_meter(&mut self, left: u8, right: u8, _volume: u8) {
-        if self.display_mode == DisplayMode::BigInfo {
+        if matches!(
+            self.display_mode,
+            DisplayMode::BigInfo | DisplayMode::ArtInfo | DisplayMode::Spectrum
+        ) || self.list.is_some()
+        {
             return;
         }
         let h_left = (f32::from(left) / 255.0 * VU_MAX_HEIGHT as f32) as u32;
@@ -665,6 +1262,57 @@ where
         }
     }
 
+    /// Takes a position update from the host; the progress bar then runs
+    /// on its own until the next one.
+    pub fn set_position(&mut self, position_ms: u32, duration_ms: u32, playing: bool) {
+        self.clock = Some(PlaybackClock {
+            position_ms,
+            duration_ms,
+            playing,
+            at: Instant::now(),
+            drawn: None,
+        });
+        self.update_clock();
+    }
+
+    /// Stops the local clock, e.g. when leaving the player input or when
+    /// the host sends preformatted progress instead.
+    pub fn clear_position(&mut self) {
+        self.clock = None;
+    }
+
+    /// Redraws the progress bar from the clock when the shown second or the
+    /// bar width has moved on.
+    fn update_clock(&mut self) {
+        if self.display_mode != DisplayMode::Normal || self.list.is_some() {
+            return;
+        }
+        let Some(clock) = self.clock.as_mut() else {
+            return;
+        };
+        let pos = clock.position_ms();
+        let progress = if clock.duration_ms > 0 {
+            pos as f32 / clock.duration_ms as f32
+        } else {
+            0.0
+        };
+        let drawn = (pos / 1000, (progress * BAR_WIDTH as f32) as u32);
+        if clock.drawn == Some(drawn) {
+            return;
+        }
+        clock.drawn = Some(drawn);
+        let total = format_ms(clock.duration_ms);
+        self.draw_progress_bar(&format_ms(pos), &total, progress);
+    }
+
+    /// Makes the next tick redraw the progress bar, after it was painted
+    /// over.
+    fn invalidate_clock(&mut self) {
+        if let Some(clock) = self.clock.as_mut() {
+            clock.drawn = None;
+        }
+    }
+
     pub fn draw_bar(&mut self, progress: f32) {
         let buffer_slice = unsafe { &mut LINE_BUFFER[..(BAR_WIDTH * BAR_HEIGHT) as usize] };
         buffer_slice.fill(COL_BG_BASE); // Default background
@@ -782,6 +1430,9 @@ where
     }
 
     pub fn draw_progress_bar(&mut self, curr_time: &str, total_time: &str, progress: f32) {
+        if self.list.is_some() {
+            return;
+        }
         self.draw_bar(progress);
         self.draw_current_time(curr_time);
         if self.last_total_time != total_time {
@@ -793,6 +1444,10 @@ where
 
     pub fn draw_powered_off(&mut self) {
         self.invalidate_vu();
+        self.list = None;
+        self.toast = None;
+        self.toasts.clear();
+        self.clock = None;
         let font_huge = FontRenderer::new::<fonts::u8g2_font_fub42_tf>();
 
         Rectangle::new(Point::new(0, 0), self.display.size())
@@ -812,8 +1467,28 @@ where
         );
     }
 
+    /// One line under "Off" on the standby screen.
+    pub fn draw_reset_reason(&mut self, text: &str) {
+        let center = self.display.bounding_box().center();
+        Text::with_text_style(
+            text,
+            Point::new(center.x, center.y + 60),
+            U8g2TextStyle::new(fonts::u8g2_font_helvB18_tf, COL_TEXT),
+            TextStyleBuilder::new()
+                .alignment(Alignment::Center)
+                .baseline(Baseline::Middle)
+                .build(),
+        )
+        .draw(&mut self.display)
+        .ok();
+    }
+
     pub fn clear_main_area(&mut self) {
+        if self.list.is_some() {
+            return;
+        }
         self.invalidate_vu();
+        self.invalidate_clock();
         let y_start = if self.display_mode == DisplayMode::BigInfo {
             0
         } else {
@@ -833,6 +1508,9 @@ where
         const MAX_WIDTH: u32 = 400;
         const L_Y: i32 = 100;
         const R_Y: i32 = 180;
+        if self.list.is_some() {
+            return;
+        }
 
         let w_left = (f32::from(left) / 255.0 * MAX_WIDTH as f32) as u32;
         let w_right = (f32::from(right) / 255.0 * MAX_WIDTH as f32) as u32;
@@ -881,7 +1559,100 @@ where
         }
     }
 
+    /// Draws one spectrum frame, `bands` magnitudes from low to high
+    /// frequency.
+    pub fn draw_spectrum(&mut self, bands: &[u8]) {
+        if self.display_mode != DisplayMode::Spectrum || self.list.is_some() || bands.is_empty() {
+            return;
+        }
+        let n = bands.len().min(SPECTRUM_MAX_BANDS);
+        let slot = SPECTRUM_WIDTH / n as u32;
+        let bar_width = slot.saturating_sub(SPECTRUM_GAP).max(1);
+        let start_x = SPECTRUM_X + (SPECTRUM_WIDTH - slot * n as u32) as i32 / 2;
+
+        // Same delta scheme as the VU bars: up to 32 bars of 12×193 px at
+        // the host frame rate would saturate the SPI bus, while only the
+        // spans between old and new level and the caps actually change.
+        if self.last_spectrum.len() != n {
+            Rectangle::new(
+                Point::new(
+                    SPECTRUM_X,
+                    SPECTRUM_BOTTOM_Y - (SPECTRUM_MAX_HEIGHT + SPECTRUM_CAP) as i32,
+                ),
+                Size::new(SPECTRUM_WIDTH, SPECTRUM_MAX_HEIGHT + SPECTRUM_CAP),
+            )
+            .into_styled(embedded_graphics::primitives::PrimitiveStyle::with_fill(
+                COL_BG_BASE,
+            ))
+            .draw(&mut self.display)
+            .ok();
+            self.last_spectrum.clear();
+            self.last_spectrum.resize(n, (0, 0)).ok();
+        }
+
+        for (i, &band) in bands[..n].iter().enumerate() {
+            let x = start_x + (i as u32 * slot) as i32;
+            let level = (f32::from(band) / 255.0 * SPECTRUM_MAX_HEIGHT as f32) as u32;
+            let (old_level, old_peak) = self.last_spectrum[i];
+            let peak = level.max(old_peak.saturating_sub(SPECTRUM_PEAK_FALL));
+            self.last_spectrum[i] = (level, peak);
+
+            self.draw_spectrum_span(
+                x,
+                bar_width,
+                old_level.min(level),
+                old_level.max(level),
+                level,
+            );
+            if peak != old_peak {
+                // Old cap: bar or background, whichever is under it now.
+                self.draw_spectrum_span(x, bar_width, old_peak, old_peak + SPECTRUM_CAP, level);
+            }
+            Rectangle::new(
+                Point::new(x, SPECTRUM_BOTTOM_Y - (peak + SPECTRUM_CAP) as i32),
+                Size::new(bar_width, SPECTRUM_CAP),
+            )
+            .into_styled(embedded_graphics::primitives::PrimitiveStyle::with_fill(
+                Rgb666::WHITE,
+            ))
+            .draw(&mut self.display)
+            .ok();
+        }
+    }
+
+    /// Repaints rows `from..to` (px above the bottom) of one spectrum bar at
+    /// `x`, filled up to `level`: zone colors below the level, background
+    /// above it.
+    fn draw_spectrum_span(&mut self, x: i32, width: u32, from: u32, to: u32, level: u32) {
+        let green_limit = (SPECTRUM_MAX_HEIGHT as f32 * 0.7) as u32;
+        let orange_limit = (SPECTRUM_MAX_HEIGHT as f32 * 0.9) as u32;
+        let zones = [
+            (0, green_limit.min(level), COL_1),
+            (green_limit.min(level), orange_limit.min(level), COL_TEXT),
+            (orange_limit.min(level), level, COL_VU_MAX),
+            (level, SPECTRUM_MAX_HEIGHT + SPECTRUM_CAP, COL_BG_BASE),
+        ];
+        for (z_start, z_end, color) in zones {
+            let s = from.max(z_start);
+            let e = to.min(z_end);
+            if e > s {
+                Rectangle::new(
+                    Point::new(x, SPECTRUM_BOTTOM_Y - e as i32),
+                    Size::new(width, e - s),
+                )
+                .into_styled(embedded_graphics::primitives::PrimitiveStyle::with_fill(
+                    color,
+                ))
+                .draw(&mut self.display)
+                .ok();
+            }
+        }
+    }
+
     pub fn draw_fullscreen_vu_labels(&mut self) {
+        if self.list.is_some() {
+            return;
+        }
         let max_width: u32 = 400;
         let start_x: i32 = (480 - max_width as i32) / 2;
         let l_y: i32 = 100;
@@ -897,6 +1668,9 @@ where
     }
 
     pub fn draw_large_volume(&mut self, vol: u8) {
+        if self.list.is_some() {
+            return;
+        }
         let width = 350;
         let height = 60;
 
@@ -1102,6 +1876,14 @@ mod hardware {
             self.player_display.draw_track_info(title, artist, album);
         }
 
+        pub fn draw_track_ext(&mut self, ext: &TrackExt) {
+            self.player_display.draw_track_ext(ext);
+        }
+
+        pub fn draw_up_next(&mut self, title: &str, artist: &str) {
+            self.player_display.draw_up_next(title, artist);
+        }
+
         pub fn redraw_track_info(&mut self) {
             self.player_display.redraw_track_info();
         }
@@ -1110,6 +1892,25 @@ mod hardware {
             self.player_display.clear_track_info();
         }
 
+        pub fn open_list(&mut self) {
+            self.player_display.open_list();
+        }
+
+        pub fn draw_list<'a>(
+            &mut self,
+            title: &str,
+            rows: impl IntoIterator<Item = &'a str>,
+            selected: Option<usize>,
+            playing: Option<usize>,
+        ) {
+            self.player_display
+                .draw_list(title, rows, selected, playing);
+        }
+
+        pub fn close_list(&mut self) {
+            self.player_display.close_list();
+        }
+
     pub fn draw_vu_meter(&mut self, left: u8, right: u8, volume: u8) {
             self.player_display.draw_vu_meter(left, right, volume);
         }
@@ -1119,6 +1920,15 @@ mod hardware {
                 .draw_progress_bar(curr_time, total_time, progress);
         }
 
+        pub fn set_position(&mut self, position_ms: u32, duration_ms: u32, playing: bool) {
+            self.player_display
+                .set_position(position_ms, duration_ms, playing);
+        }
+
+        pub fn clear_position(&mut self) {
+            self.player_display.clear_position();
+        }
+
         pub fn draw_footer(&mut self, format: &str, freq: &str, bit_depth: &str) {
             self.player_display.draw_footer(format, freq, bit_depth);
         }
@@ -1127,10 +1937,38 @@ mod hardware {
             self.player_display.redraw_footer();
         }
 
+        pub fn draw_host_status(&mut self, online: bool) {
+            self.player_display.draw_host_status(online);
+        }
+
+        pub fn notify(&mut self, text: &str, severity: Severity, duration_ms: u16) {
+            self.player_display.notify(text, severity, duration_ms);
+        }
+
         pub fn draw_powered_off(&mut self) {
             self.player_display.draw_powered_off();
         }
 
+        pub fn draw_reset_reason(&mut self, text: &str) {
+            self.player_display.draw_reset_reason(text);
+        }
+
+        pub fn draw_art(
+            &mut self,
+            width: u16,
+            height: u16,
+            format: ArtFormat,
+            first_px: u32,
+            data: &[u8],
+        ) {
+            self.player_display
+                .draw_art(width, height, format, first_px, data);
+        }
+
+        pub fn clear_art(&mut self) {
+            self.player_display.clear_art();
+        }
+
         pub fn clear_main_area(&mut self) {
             self.player_display.clear_main_area();
         }
@@ -1144,6 +1982,10 @@ mod hardware {
             self.player_display.draw_fullscreen_vu_labels();
         }
 
+        pub fn draw_spectrum(&mut self, bands: &[u8]) {
+            self.player_display.draw_spectrum(bands);
+        }
+
         pub fn draw_large_volume(&mut self, vol: u8) {
             self.player_display.draw_large_volume(vol);
         }
@@ -212,6 +212,34 @@ impl OledDisplay {
     pub fn draw_background(&mut self) {}
     pub fn draw_layout_lines(&mut self) {}
     pub fn draw_track_info(&mut self, _title: &str, _artist: &str, _album: &str) {}
+    pub fn draw_track_ext(&mut self, _ext: &rsplayer_wire::TrackExt) {}
+    pub fn draw_up_next(&mut self, _title: &str, _artist: &str) {}
     pub fn draw_progress_bar(&mut self, _curr_time: &str, _total_time: &str, _progress: f32) {}
+    pub fn set_position(&mut self, _position_ms: u32, _duration_ms: u32, _playing: bool) {}
+    pub fn clear_position(&mut self) {}
     pub fn draw_footer(&mut self, _format: &str, _freq: &str, _bit_depth: &str) {}
+    pub fn draw_host_status(&mut self, _online: bool) {}
+    pub fn notify(&mut self, _text: &str, _severity: rsplayer_wire::Severity, _duration_ms: u16) {}
+    pub fn draw_reset_reason(&mut self, _text: &str) {}
+    pub fn draw_spectrum(&mut self, _bands: &[u8]) {}
+    pub fn draw_art(
+        &mut self,
+        _width: u16,
+        _height: u16,
+        _format: crate::art::ArtFormat,
+        _first_px: u32,
+        _data: &[u8],
+    ) {
+    }
+    pub fn clear_art(&mut self) {}
+    pub fn open_list(&mut self) {}
+    pub fn draw_list<'a>(
+        &mut self,
+        _title: &str,
+        _rows: impl IntoIterator<Item = &'a str>,
+        _selected: Option<usize>,
+        _playing: Option<usize>,
+    ) {
+    }
+    pub fn close_list(&mut self) {}
 }
@@ -1,11 +1,18 @@
+use core::cell::RefCell;
+
 use embassy_rp::{
     flash::{Async, Flash, ERASE_SIZE},
     peripherals::FLASH,
 };
+use embassy_sync::blocking_mutex::{raw::CriticalSectionRawMutex, Mutex};
+use static_cell::StaticCell;
 
+use crate::hostlog::host_log;
+use crate::input::InputTable;
 use crate::FlashResources;
 
-const FLASH_SIZE: usize = 2 * 1024 * 1024;
+pub const FLASH_SIZE: usize = 2 * 1024 * 1024;
+/// Settings live past the end of the DFU partition (see memory.x).
 const ADDR_OFFSET: u32 = 0x100000;
 
 const VOLUME_OFFSET: u32 = 0x00;
@@ -13,16 +20,36 @@ const INPUT_OFFSET: u32 = 0x1000;
 const FILTER_OFFSET: u32 = 0x2000;
 const SOUND_OFFSET: u32 = 0x3000;
 const DISPLAY_MODE_OFFSET: u32 = 0x4000;
+const INPUT_TABLE_OFFSET: u32 = 0x5000;
+const INPUT_POLICY_OFFSET: u32 = 0x6000;
+const HID_MODE_OFFSET: u32 = 0x7000;
+const HOST_RECOVERY_OFFSET: u32 = 0x8000;
+
+/// Blob sectors start with a little-endian u16 payload length; erased flash
+/// reads 0xFFFF there, which is treated as "nothing stored".
+const BLOB_HEADER: usize = 2;
+
+pub type FlashDevice = Flash<'static, FLASH, Async, FLASH_SIZE>;
+
+/// The flash is shared by the settings storage and the firmware updater.
+pub type SharedFlash = Mutex<CriticalSectionRawMutex, RefCell<FlashDevice>>;
+
+static FLASH: StaticCell<SharedFlash> = StaticCell::new();
 
 pub struct Storage {
-    flash: Flash<'static, FLASH, Async, FLASH_SIZE>,
+    flash: &'static SharedFlash,
 }
 impl Storage {
     pub fn new(res: FlashResources) -> Self {
+        let flash = embassy_rp::flash::Flash::<_, Async, FLASH_SIZE>::new(res.flash, res.dma_ch4);
         Storage {
-            flash: embassy_rp::flash::Flash::<_, Async, FLASH_SIZE>::new(res.flash, res.dma_ch4),
+            flash: FLASH.init(Mutex::new(RefCell::new(flash))),
         }
     }
+
+    pub fn shared(&self) -> &'static SharedFlash {
+        self.flash
+    }
     pub fn save_volume(&mut self, volume: u8) {
         self.write_u8(VOLUME_OFFSET, volume);
     }
@@ -55,19 +82,89 @@ impl Storage {
         self.read_u8(DISPLAY_MODE_OFFSET)
     }
 
+    pub fn save_input_table(&mut self, table: &InputTable) {
+        self.write_blob(INPUT_TABLE_OFFSET, table);
+    }
+    /// `None` when nothing valid is stored — callers fall back to defaults.
+    /// A blob that decodes but fails `InputTable::is_valid` (old layout,
+    /// corrupted sector) counts as nothing stored.
+    pub fn load_input_table(&mut self) -> Option<InputTable> {
+        self.read_blob(INPUT_TABLE_OFFSET)
+            .filter(InputTable::is_valid)
+    }
+
+    pub fn save_input_policy(&mut self, policy: u8) {
+        self.write_u8(INPUT_POLICY_OFFSET, policy);
+    }
+    pub fn load_input_policy(&mut self) -> u8 {
+        self.read_u8(INPUT_POLICY_OFFSET)
+    }
+
+    pub fn save_hid_mode(&mut self, mode: u8) {
+        self.write_u8(HID_MODE_OFFSET, mode);
+    }
+    pub fn load_hid_mode(&mut self) -> u8 {
+        self.read_u8(HID_MODE_OFFSET)
+    }
+
+    /// Seconds past the host timeout before the host is power-cycled;
+    /// 0 (also what erased flash gives) disables the recovery.
+    pub fn save_host_recovery(&mut self, secs: u16) {
+        self.write_blob(HOST_RECOVERY_OFFSET, &secs);
+    }
+    pub fn load_host_recovery(&mut self) -> u16 {
+        self.read_blob(HOST_RECOVERY_OFFSET).unwrap_or(0)
+    }
+
     fn write_u8(&mut self, offset: u32, data: u8) {
         let mut bytes = [0; ERASE_SIZE];
         bytes[0] = data;
-        defmt::unwrap!(self.flash.blocking_erase(
-            ADDR_OFFSET + offset,
-            ADDR_OFFSET + offset + bytes.len() as u32
-        ));
-        defmt::unwrap!(self.flash.blocking_write(ADDR_OFFSET + offset, &bytes));
+        self.write_sector(offset, &bytes);
     }
 
     fn read_u8(&mut self, offset: u32) -> u8 {
         let mut bytes = [0; ERASE_SIZE];
-        defmt::unwrap!(self.flash.blocking_read(ADDR_OFFSET + offset, &mut bytes));
+        self.read(offset, &mut bytes);
         bytes[0]
     }
+
+    fn write_blob<T: serde::Serialize>(&mut self, offset: u32, value: &T) {
+        let mut bytes = [0xFF; ERASE_SIZE];
+        let len = match postcard::to_slice(value, &mut bytes[BLOB_HEADER..]) {
+            Ok(used) => used.len() as u16,
+            Err(_) => {
+                host_log!(Error, "Settings blob too large for one sector");
+                return;
+            }
+        };
+        bytes[..BLOB_HEADER].copy_from_slice(&len.to_le_bytes());
+        self.write_sector(offset, &bytes);
+    }
+
+    fn read_blob<T: serde::de::DeserializeOwned>(&mut self, offset: u32) -> Option<T> {
+        let mut bytes = [0; ERASE_SIZE];
+        self.read(offset, &mut bytes);
+        let len = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+        if len > ERASE_SIZE - BLOB_HEADER {
+            return None;
+        }
+        postcard::from_bytes(&bytes[BLOB_HEADER..BLOB_HEADER + len]).ok()
+    }
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) {
+        self.flash.lock(|f| {
+            defmt::unwrap!(f.borrow_mut().blocking_read(ADDR_OFFSET + offset, bytes));
+        });
+    }
+
+    fn write_sector(&mut self, offset: u32, bytes: &[u8; ERASE_SIZE]) {
+        self.flash.lock(|f| {
+            let mut f = f.borrow_mut();
+            defmt::unwrap!(f.blocking_erase(
+                ADDR_OFFSET + offset,
+                ADDR_OFFSET + offset + bytes.len() as u32
+            ));
+            defmt::unwrap!(f.blocking_write(ADDR_OFFSET + offset, bytes));
+        });
+    }
 }
@@ -0,0 +1,233 @@
+use core::marker::PhantomData;
+
+use heapless::Vec;
+use serde::de::DeserializeOwned;
+
+/// Why a frame did not yield a message.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
+pub enum FrameError {
+    /// More than `N` bytes without a delimiter; the rest of the frame is
+    /// skipped up to the next 0x00.
+    Overflow,
+    /// Bad COBS encoding or a postcard payload that is not a `T`.
+    Decode,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, defmt::Format)]
+pub struct FrameStats {
+    pub frames: u32,
+    pub overflows: u32,
+    pub decode_errors: u32,
+}
+
+/// Accumulates a byte stream of 0x00-delimited COBS frames and decodes each
+/// into a postcard `T`. Knows nothing about the transport, so it can be fed
+/// from USB packets, a UART or a test vector alike.
+pub struct FrameDecoder<T, const N: usize> {
+    buf: Vec<u8, N>,
+    /// Set after an overflow until the delimiter that ends the bad frame.
+    skipping: bool,
+    stats: FrameStats,
+    _msg: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned, const N: usize> FrameDecoder<T, N> {
+    pub const fn new() -> Self {
+        FrameDecoder {
+            buf: Vec::new(),
+            skipping: false,
+            stats: FrameStats {
+                frames: 0,
+                overflows: 0,
+                decode_errors: 0,
+            },
+            _msg: PhantomData,
+        }
+    }
+
+    /// Drops a partially received frame, e.g. after the link went down.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.skipping = false;
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+
+    /// Consumes one byte; returns a result whenever a frame ends or
+    /// overflows. Empty frames (back-to-back delimiters) are skipped.
+    pub fn push(&mut self, byte: u8) -> Option<Result<T, FrameError>> {
+        if byte == 0x00 {
+            let skipped = core::mem::replace(&mut self.skipping, false);
+            if skipped || self.buf.is_empty() {
+                self.buf.clear();
+                return None;
+            }
+            // Decoded in place, the buffer is garbage afterwards either way.
+            let res = postcard::from_bytes_cobs::<T>(&mut self.buf);
+            self.buf.clear();
+            return Some(match res {
+                Ok(msg) => {
+                    self.stats.frames = self.stats.frames.wrapping_add(1);
+                    Ok(msg)
+                }
+                Err(_) => {
+                    self.stats.decode_errors = self.stats.decode_errors.wrapping_add(1);
+                    Err(FrameError::Decode)
+                }
+            });
+        }
+        if self.skipping {
+            return None;
+        }
+        if self.buf.push(byte).is_err() {
+            self.buf.clear();
+            self.skipping = true;
+            self.stats.overflows = self.stats.overflows.wrapping_add(1);
+            return Some(Err(FrameError::Overflow));
+        }
+        None
+    }
+
+    /// Feeds a chunk of any size, yielding every frame it completes.
+    pub fn feed<'a>(
+        &'a mut self,
+        chunk: &'a [u8],
+    ) -> impl Iterator<Item = Result<T, FrameError>> + 'a {
+        chunk.iter().filter_map(move |&b| self.push(b))
+    }
+}
+
+impl<T: DeserializeOwned, const N: usize> Default for FrameDecoder<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec as StdVec;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Msg {
+        id: u8,
+        value: u32,
+    }
+
+    /// Room for a `Msg` plus COBS overhead, but not for the long frame.
+    const N: usize = 16;
+
+    fn encode(msg: &Msg) -> StdVec<u8> {
+        let mut buf = [0u8; 32];
+        postcard::to_slice_cobs(msg, &mut buf).unwrap().to_vec()
+    }
+
+    fn msg(id: u8) -> Msg {
+        Msg {
+            id,
+            value: 0x0100_0000 * id as u32 + 7,
+        }
+    }
+
+    fn feed_all(
+        decoder: &mut FrameDecoder<Msg, N>,
+        bytes: &[u8],
+    ) -> StdVec<Result<Msg, FrameError>> {
+        decoder.feed(bytes).collect()
+    }
+
+    #[test]
+    fn several_frames_in_one_chunk() {
+        let mut decoder = FrameDecoder::<Msg, N>::new();
+        let mut chunk = encode(&msg(1));
+        chunk.push(0x00);
+        chunk.extend(encode(&msg(2)));
+        chunk.extend(encode(&msg(3)));
+        assert_eq!(
+            feed_all(&mut decoder, &chunk),
+            [Ok(msg(1)), Ok(msg(2)), Ok(msg(3))]
+        );
+        assert_eq!(decoder.stats().frames, 3);
+    }
+
+    #[test]
+    fn frame_split_across_chunks() {
+        let bytes = encode(&msg(4));
+        for split in 0..=bytes.len() {
+            let mut decoder = FrameDecoder::<Msg, N>::new();
+            let (a, b) = bytes.split_at(split);
+            let mut got = feed_all(&mut decoder, a);
+            got.extend(feed_all(&mut decoder, b));
+            assert_eq!(got, [Ok(msg(4))], "split at {}", split);
+        }
+        // One byte per chunk, as a slow UART would deliver it.
+        let mut decoder = FrameDecoder::<Msg, N>::new();
+        let got: StdVec<_> = bytes.iter().filter_map(|&b| decoder.push(b)).collect();
+        assert_eq!(got, [Ok(msg(4))]);
+    }
+
+    #[test]
+    fn overflow_recovers_at_next_delimiter() {
+        let mut decoder = FrameDecoder::<Msg, N>::new();
+        let mut chunk = StdVec::from([0x55u8; N + 8]);
+        chunk.push(0x00);
+        chunk.extend(encode(&msg(5)));
+        assert_eq!(
+            feed_all(&mut decoder, &chunk),
+            [Err(FrameError::Overflow), Ok(msg(5))]
+        );
+        assert_eq!(
+            decoder.stats(),
+            FrameStats {
+                frames: 1,
+                overflows: 1,
+                decode_errors: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn overflow_reported_once_per_frame() {
+        let mut decoder = FrameDecoder::<Msg, N>::new();
+        let long = [0x55u8; 4 * N];
+        let mut got = feed_all(&mut decoder, &long);
+        // The tail of the bad frame arrives in a later chunk.
+        got.extend(feed_all(&mut decoder, &long));
+        assert_eq!(got, [Err(FrameError::Overflow)]);
+        assert_eq!(feed_all(&mut decoder, &[0x00]), []);
+        assert_eq!(feed_all(&mut decoder, &encode(&msg(6))), [Ok(msg(6))]);
+    }
+
+    #[test]
+    fn decode_error_then_resync() {
+        let mut decoder = FrameDecoder::<Msg, N>::new();
+        // A COBS code byte pointing past the end of the frame.
+        let mut chunk = StdVec::from([0x09u8, 0x01, 0x00]);
+        // Valid COBS, but one byte is not a `Msg`.
+        chunk.extend([0x02, 0x01, 0x00]);
+        chunk.extend(encode(&msg(7)));
+        assert_eq!(
+            feed_all(&mut decoder, &chunk),
+            [Err(FrameError::Decode), Err(FrameError::Decode), Ok(msg(7))]
+        );
+        assert_eq!(decoder.stats().decode_errors, 2);
+    }
+
+    #[test]
+    fn empty_frames_and_reset() {
+        let mut decoder = FrameDecoder::<Msg, N>::new();
+        assert_eq!(feed_all(&mut decoder, &[0x00, 0x00, 0x00]), []);
+        let bytes = encode(&msg(8));
+        // A link drop mid-frame must not glue the stale half onto the next.
+        assert_eq!(feed_all(&mut decoder, &bytes[..3]), []);
+        decoder.reset();
+        assert_eq!(feed_all(&mut decoder, &bytes), [Ok(msg(8))]);
+    }
+}
@@ -0,0 +1,145 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use defmt::debug;
+use embassy_rp::{peripherals::USB, usb::Driver};
+use embassy_time::{with_timeout, Duration};
+use embassy_usb::class::hid::{Config, HidBootProtocol, HidSubclass, HidWriter};
+use rsplayer_wire::FwPlayerCmd;
+
+/// Where player commands (play, next, seek, ...) go.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
+pub enum HidMode {
+    /// rsplayer wire protocol only.
+    RsPlayer = 0,
+    /// Standard media keys only, for any other player on the host.
+    Hid = 1,
+    Both = 2,
+    /// Like `Hid`, and the volume controls step the host mixer with
+    /// volume and mute keys instead of the DAC.
+    HidVolume = 3,
+}
+
+impl From<u8> for HidMode {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => HidMode::Hid,
+            2 => HidMode::Both,
+            3 => HidMode::HidVolume,
+            _ => HidMode::RsPlayer,
+        }
+    }
+}
+
+impl HidMode {
+    pub fn rsplayer(self) -> bool {
+        matches!(self, HidMode::RsPlayer | HidMode::Both)
+    }
+
+    pub fn hid(self) -> bool {
+        self != HidMode::RsPlayer
+    }
+
+    pub fn host_volume(self) -> bool {
+        self == HidMode::HidVolume
+    }
+}
+
+/// Mirrors `HidMode::host_volume` of the current mode for the IR task,
+/// which only then maps a button to mute.
+pub static HOST_VOLUME: AtomicBool = AtomicBool::new(false);
+
+pub fn publish_mode(mode: HidMode) {
+    HOST_VOLUME.store(mode.host_volume(), Ordering::Relaxed);
+}
+
+/// A key on the consumer-control interface.
+#[derive(Clone)]
+pub enum MediaKey {
+    Player(FwPlayerCmd),
+    VolumeUp,
+    VolumeDown,
+    Mute,
+}
+
+/// Consumer control collection with a single 16-bit usage: the report is
+/// the usage of the key held down, 0 once released. The usage range covers
+/// every key in `usage`, transport (0xB3..=0xCD) and volume (0xE2..=0xEA).
+const REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x0C, // Usage Page (Consumer)
+    0x09, 0x01, // Usage (Consumer Control)
+    0xA1, 0x01, // Collection (Application)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x26, 0xFF, 0x03, //   Logical Maximum (0x3FF)
+    0x19, 0x00, //   Usage Minimum (0)
+    0x2A, 0xFF, 0x03, //   Usage Maximum (0x3FF)
+    0x75, 0x10, //   Report Size (16)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x00, //   Input (Data, Array, Absolute)
+    0xC0, // End Collection
+];
+
+pub fn config() -> Config<'static> {
+    Config {
+        report_descriptor: REPORT_DESCRIPTOR,
+        request_handler: None,
+        poll_ms: 10,
+        max_packet_size: 8,
+        hid_subclass: HidSubclass::No,
+        hid_boot_protocol: HidBootProtocol::None,
+    }
+}
+
+const USAGE_FAST_FORWARD: u16 = 0xB3;
+const USAGE_REWIND: u16 = 0xB4;
+const USAGE_NEXT_TRACK: u16 = 0xB5;
+const USAGE_PREV_TRACK: u16 = 0xB6;
+const USAGE_STOP: u16 = 0xB7;
+const USAGE_PLAY_PAUSE: u16 = 0xCD;
+const USAGE_MUTE: u16 = 0xE2;
+const USAGE_VOLUME_UP: u16 = 0xE9;
+const USAGE_VOLUME_DOWN: u16 = 0xEA;
+
+/// Usage for `key`, if it has one. The volume keys are only queued in
+/// `HidMode::HidVolume`, where the DAC is left alone — turning both would
+/// double the step.
+fn usage(key: &MediaKey) -> Option<u16> {
+    match key {
+        MediaKey::Player(FwPlayerCmd::TogglePlay) => Some(USAGE_PLAY_PAUSE),
+        MediaKey::Player(FwPlayerCmd::Next) => Some(USAGE_NEXT_TRACK),
+        MediaKey::Player(FwPlayerCmd::Prev) => Some(USAGE_PREV_TRACK),
+        MediaKey::Player(FwPlayerCmd::SeekForward) => Some(USAGE_FAST_FORWARD),
+        MediaKey::Player(FwPlayerCmd::SeekBackward) => Some(USAGE_REWIND),
+        MediaKey::Player(FwPlayerCmd::Stop) => Some(USAGE_STOP),
+        MediaKey::Player(_) => None,
+        MediaKey::VolumeUp => Some(USAGE_VOLUME_UP),
+        MediaKey::VolumeDown => Some(USAGE_VOLUME_DOWN),
+        MediaKey::Mute => Some(USAGE_MUTE),
+    }
+}
+
+pub struct MediaKeys {
+    writer: HidWriter<'static, Driver<'static, USB>, 2>,
+}
+
+impl MediaKeys {
+    pub fn new(writer: HidWriter<'static, Driver<'static, USB>, 2>) -> Self {
+        MediaKeys { writer }
+    }
+
+    /// Sends a press and release of `key`, if it has a usage.
+    pub async fn tap(&mut self, key: &MediaKey) {
+        let Some(usage) = usage(key) else {
+            return;
+        };
+        // A host without a HID driver never polls the endpoint.
+        for report in [usage.to_le_bytes(), [0, 0]] {
+            if with_timeout(Duration::from_millis(50), self.writer.write(&report))
+                .await
+                .map_or(true, |r| r.is_err())
+            {
+                debug!("HID report not taken by host");
+                return;
+            }
+        }
+    }
+}
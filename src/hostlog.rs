@@ -0,0 +1,51 @@
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use defmt::Display2Format;
+use embassy_time::Instant;
+use heapless::String;
+use rsplayer_wire::{FwToHost, LogLevel, LOG_TEXT_LEN};
+
+use crate::rsplayer::HOST_OUTBOX;
+
+/// Most verbose level still sent to the host, adjustable at runtime.
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+
+pub fn set_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Logs to defmt as usual and, if `level` passes the runtime filter, to the
+/// host as well. The host copy is dropped rather than waited for when the
+/// outbox is full, and truncated to `LOG_TEXT_LEN`.
+pub fn log(level: LogLevel, args: fmt::Arguments) {
+    match level {
+        LogLevel::Error => defmt::error!("{}", Display2Format(&args)),
+        LogLevel::Warn => defmt::warn!("{}", Display2Format(&args)),
+        LogLevel::Info => defmt::info!("{}", Display2Format(&args)),
+        LogLevel::Debug => defmt::debug!("{}", Display2Format(&args)),
+    }
+    if level as u8 > LEVEL.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut text: String<LOG_TEXT_LEN> = String::new();
+    // Overflow leaves what fit; a cut-off line beats none.
+    text.write_fmt(args).ok();
+    HOST_OUTBOX.push(FwToHost::Log {
+        level,
+        uptime_ms: Instant::now().as_millis() as u32,
+        text,
+    });
+}
+
+/// `host_log!(Info, "input {}", name)` — like defmt's macros, but with
+/// `core::fmt` formatting so the line can also be sent to the host. Meant
+/// for events worth a line in a user's report; bus and register tracing
+/// stays on plain defmt, which costs neither flash for the formatting nor
+/// outbox room.
+macro_rules! host_log {
+    ($level:ident, $($arg:tt)*) => {
+        $crate::hostlog::log(rsplayer_wire::LogLevel::$level, format_args!($($arg)*))
+    };
+}
+pub(crate) use host_log;
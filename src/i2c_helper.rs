@@ -1,25 +1,72 @@
+use core::cell::RefCell;
+
 use defmt::debug;
-use embassy_rp::i2c::{self, Error};
+use embassy_embedded_hal::shared_bus::blocking::i2c::I2cDevice;
+use embassy_rp::i2c::{self, Config};
+use embassy_rp::peripherals::I2C1;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
 use embassy_time::Timer;
 use embedded_hal_1::i2c::I2c;
+use static_cell::StaticCell;
+
+use crate::hostlog::host_log;
+use crate::{I2cBusResources, IrqsI2c, POWER_ON};
+
+/// I2C1 is shared by the DAC and the S/PDIF receiver; each talks to it
+/// through its own `I2CHelper` bound to the chip's address.
+pub type I2cBus = Mutex<CriticalSectionRawMutex, RefCell<i2c::I2c<'static, I2C1, i2c::Async>>>;
 
-use crate::POWER_ON;
+static I2C_BUS: StaticCell<I2cBus> = StaticCell::new();
+
+pub fn init_bus(resources: I2cBusResources) -> &'static I2cBus {
+    let i2c = i2c::I2c::new_async(
+        resources.i2c,
+        resources.pin15_i2c_scl,
+        resources.pin14_i2c_sda,
+        IrqsI2c,
+        Config::default(),
+    );
+    I2C_BUS.init(Mutex::new(RefCell::new(i2c)))
+}
 
 pub struct I2CHelper {
-    i2c: i2c::I2c<'static, embassy_rp::peripherals::I2C1, i2c::Async>,
+    i2c: I2cDevice<'static, CriticalSectionRawMutex, i2c::I2c<'static, I2C1, i2c::Async>>,
     addr: u8,
+    fault: bool,
 }
 
-const ADDR_AK4497: u8 = 0x13;
+pub const ADDR_AK4497: u8 = 0x13;
 
 impl I2CHelper {
-    pub fn new(
-        i2c: i2c::I2c<'static, embassy_rp::peripherals::I2C1, i2c::Async>,
-    ) -> Result<Self, Error> {
-        Ok(I2CHelper {
-            i2c,
-            addr: ADDR_AK4497,
-        })
+    pub fn new(bus: &'static I2cBus, addr: u8) -> Self {
+        I2CHelper {
+            i2c: I2cDevice::new(bus),
+            addr,
+            fault: false,
+        }
+    }
+
+    /// Whether the last register access failed. Cleared by the next one
+    /// that succeeds.
+    pub fn is_faulted(&self) -> bool {
+        self.fault
+    }
+
+    fn track<T, E>(&mut self, res: Result<T, E>) -> Option<T> {
+        match res {
+            Ok(v) => {
+                self.fault = false;
+                Some(v)
+            }
+            Err(_) => {
+                if !self.fault {
+                    host_log!(Error, "I2C access to {:x} failed", self.addr);
+                }
+                self.fault = true;
+                None
+            }
+        }
     }
 
     pub(crate) async fn write_register(&mut self, reg_addr: u8, value: u8) {
@@ -27,7 +74,8 @@ impl I2CHelper {
             return;
         }
         debug!("I2C write reg_addr:{}, value: {:b}", reg_addr, value);
-        self.i2c.write(self.addr, &[reg_addr, value]).unwrap();
+        let res = self.i2c.write(self.addr, &[reg_addr, value]);
+        self.track(res);
     }
 
     pub async fn read_register(&mut self, reg_addr: u8) -> u8 {
@@ -35,11 +83,35 @@ impl I2CHelper {
             return 0;
         }
         let mut data = [0u8; 1];
+        let res = self.i2c.write_read(self.addr, &[reg_addr], &mut data);
+        self.track(res);
+        data[0]
+    }
+    /// Like `read_register`, but reports a missing or unresponsive chip
+    /// instead of panicking — for optional devices on the shared bus.
+    pub async fn try_read_register(&mut self, reg_addr: u8) -> Option<u8> {
+        if !POWER_ON.load(core::sync::atomic::Ordering::Relaxed) {
+            return None;
+        }
+        let mut data = [0u8; 1];
         self.i2c
             .write_read(self.addr, &[reg_addr], &mut data)
-            .unwrap();
-        data[0]
+            .ok()
+            .map(|_| data[0])
     }
+
+    /// Fallible counterpart of `write_register`, see `try_read_register`.
+    pub async fn try_write_register(&mut self, reg_addr: u8, value: u8) -> Option<()> {
+        if !POWER_ON.load(core::sync::atomic::Ordering::Relaxed) {
+            return None;
+        }
+        debug!(
+            "I2C write addr:{}, reg_addr:{}, value: {:b}",
+            self.addr, reg_addr, value
+        );
+        self.i2c.write(self.addr, &[reg_addr, value]).ok()
+    }
+
     pub async fn change_bit(&mut self, reg_addr: u8, bit_pos: u8, value: bool) {
         if !POWER_ON.load(core::sync::atomic::Ordering::Relaxed) {
             return;
@@ -0,0 +1,301 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use embassy_rp::gpio::{Level, Output};
+use heapless::{String, Vec};
+use serde::{Deserialize, Serialize};
+
+use crate::InputSelectPins;
+
+pub const MAX_INPUTS: usize = 4;
+
+/// Short label shown in the display header — the ILI9488 header has room
+/// for six characters between "IN:" and the filter.
+pub type InputName = String<6>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format, Serialize, Deserialize)]
+pub enum InputKind {
+    /// Amanero USB receiver fed by rsplayer.
+    Usb,
+    /// Optical/coax through the S/PDIF receiver.
+    Spdif,
+    /// External I2S source, e.g. HDMI-I2S.
+    I2s,
+}
+
+/// How the firmware picks the input on its own.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
+pub enum InputPolicy {
+    /// Only the user switches inputs.
+    Manual = 0,
+    /// Follow signal activity: switch to USB when it starts playing, fall
+    /// back to S/PDIF when USB has gone silent and the receiver is locked.
+    Auto = 1,
+    /// Like `Auto`, but never leave a playing input for one that comes
+    /// later in the table order.
+    AutoPriority = 2,
+}
+
+impl InputPolicy {
+    /// The auto policies rely on seeing a stream on a deselected S/PDIF
+    /// input, which only the receiver chip can; without it only `Manual`
+    /// is offered.
+    pub fn supported(self) -> bool {
+        cfg!(feature = "wm8805") || self == InputPolicy::Manual
+    }
+}
+
+impl From<u8> for InputPolicy {
+    fn from(val: u8) -> Self {
+        match val {
+            1 => InputPolicy::Auto,
+            2 => InputPolicy::AutoPriority,
+            _ => InputPolicy::Manual,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct InputDef {
+    pub name: InputName,
+    pub kind: InputKind,
+    /// Levels driven on the select pins/relays, bit n → select pin n.
+    pub select: u8,
+    pub enabled: bool,
+}
+
+/// Inputs indexed by their stable id (the value persisted as the selected
+/// input), plus the order next/previous cycle through them.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct InputTable {
+    inputs: Vec<InputDef, MAX_INPUTS>,
+    order: Vec<u8, MAX_INPUTS>,
+}
+
+/// Builds an `InputName`, cutting over-long names instead of rejecting them.
+pub fn input_name(name: &str) -> InputName {
+    let mut n = InputName::new();
+    for c in name.chars() {
+        if n.push(c).is_err() {
+            break;
+        }
+    }
+    n
+}
+
+fn def(name: &str, kind: InputKind, select: u8, enabled: bool) -> InputDef {
+    InputDef {
+        name: input_name(name),
+        kind,
+        select,
+        enabled,
+    }
+}
+
+/// `order` lists every id below `len` exactly once, and `len` is non-zero.
+pub fn is_permutation(order: &[u8], len: usize) -> bool {
+    let mut seen = [false; MAX_INPUTS];
+    if len == 0 || order.len() != len {
+        return false;
+    }
+    order.iter().all(|&id| match seen.get_mut(id as usize) {
+        Some(s) if (id as usize) < len && !*s => {
+            *s = true;
+            true
+        }
+        _ => false,
+    })
+}
+
+impl Default for InputTable {
+    /// Ids 0 and 1 keep the meaning the old OPT/USB toggle stored in flash.
+    /// Bit 0 is the original I2S source relay (high = USB); coax and HDMI
+    /// are routed by the extra mux lines and ship disabled.
+    fn default() -> Self {
+        let mut inputs = Vec::new();
+        inputs.push(def("OPT", InputKind::Spdif, 0b000, true)).ok();
+        inputs.push(def("USB", InputKind::Usb, 0b001, true)).ok();
+        inputs.push(def("COAX", InputKind::Spdif, 0b010, false)).ok();
+        inputs.push(def("HDMI", InputKind::I2s, 0b100, false)).ok();
+        let mut order = Vec::new();
+        order.extend_from_slice(&[1, 0, 2, 3]).ok();
+        InputTable { inputs, order }
+    }
+}
+
+impl InputTable {
+    pub fn get(&self, id: u8) -> Option<&InputDef> {
+        self.inputs.get(id as usize)
+    }
+
+    pub fn is_enabled(&self, id: u8) -> bool {
+        self.get(id).is_some_and(|d| d.enabled)
+    }
+
+    /// Whether a table read back from flash is usable: at least one input,
+    /// the order listing every id exactly once and something enabled.
+    pub fn is_valid(&self) -> bool {
+        is_permutation(&self.order, self.inputs.len()) && self.inputs.iter().any(|d| d.enabled)
+    }
+
+    /// First enabled input in cycling order — the fallback when the stored
+    /// selection is gone or disabled.
+    pub fn first_enabled(&self) -> u8 {
+        self.order
+            .iter()
+            .copied()
+            .find(|&id| self.is_enabled(id))
+            .unwrap_or(0)
+    }
+
+    /// First enabled input of `kind` in table order.
+    pub fn first_of_kind(&self, kind: InputKind) -> Option<u8> {
+        self.order.iter().copied().find(|&id| {
+            self.get(id)
+                .is_some_and(|d| d.enabled && d.kind == kind)
+        })
+    }
+
+    /// Position in the table order; lower means higher priority.
+    pub fn rank(&self, id: u8) -> usize {
+        self.order.iter().position(|&o| o == id).unwrap_or(usize::MAX)
+    }
+
+    pub fn next(&self, current: u8) -> u8 {
+        self.step(current, 1)
+    }
+
+    pub fn prev(&self, current: u8) -> u8 {
+        self.step(current, self.order.len().saturating_sub(1))
+    }
+
+    fn step(&self, current: u8, by: usize) -> u8 {
+        let len = self.order.len();
+        if len == 0 {
+            return current;
+        }
+        let pos = self.order.iter().position(|&id| id == current).unwrap_or(0);
+        (1..=len)
+            .map(|i| self.order[(pos + i * by) % len])
+            .find(|&id| self.is_enabled(id))
+            .unwrap_or(current)
+    }
+
+    pub fn set_name(&mut self, id: u8, name: &str) -> bool {
+        match self.inputs.get_mut(id as usize) {
+            Some(d) if !name.is_empty() => {
+                d.name = input_name(name);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Refuses to disable the last enabled input.
+    pub fn set_enabled(&mut self, id: u8, enabled: bool) -> bool {
+        if !enabled && self.inputs.iter().filter(|d| d.enabled).count() <= 1 {
+            return false;
+        }
+        match self.inputs.get_mut(id as usize) {
+            Some(d) => {
+                d.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Makes the present/enabled ids visible to [`InputMask::published`].
+    /// Called by the command loop whenever they change.
+    pub fn publish(&self) {
+        let mut mask = 0;
+        for (id, d) in self.inputs.iter().enumerate() {
+            mask |= bit(id as u8);
+            if d.enabled {
+                mask |= bit(id as u8) << 4;
+            }
+        }
+        PUBLISHED_MASK.store(mask, Ordering::Relaxed);
+    }
+
+    /// Accepts only a permutation of all input ids.
+    pub fn set_order(&mut self, order: &[u8]) -> bool {
+        if !is_permutation(order, self.inputs.len()) {
+            return false;
+        }
+        self.order.clear();
+        self.order.extend_from_slice(order).ok();
+        true
+    }
+}
+
+/// Present ids in the low nibble, enabled ids in the high one.
+static PUBLISHED_MASK: AtomicU8 = AtomicU8::new(0);
+
+fn bit(id: u8) -> u8 {
+    if (id as usize) < MAX_INPUTS {
+        1 << id
+    } else {
+        0
+    }
+}
+
+/// Copy of the command loop's table as seen from other tasks, so the USB
+/// task can refuse input commands the loop would ignore before acking them.
+#[derive(Clone, Copy)]
+pub struct InputMask {
+    present: u8,
+    enabled: u8,
+}
+
+impl InputMask {
+    pub fn published() -> Self {
+        let mask = PUBLISHED_MASK.load(Ordering::Relaxed);
+        InputMask {
+            present: mask & 0x0f,
+            enabled: mask >> 4,
+        }
+    }
+
+    pub fn len(self) -> usize {
+        self.present.count_ones() as usize
+    }
+
+    pub fn contains(self, id: u8) -> bool {
+        self.present & bit(id) != 0
+    }
+
+    pub fn is_enabled(self, id: u8) -> bool {
+        self.enabled & bit(id) != 0
+    }
+
+    /// Mirrors `InputTable::set_enabled`, which never disables the last
+    /// enabled input.
+    pub fn can_set_enabled(self, id: u8, enabled: bool) -> bool {
+        self.contains(id) && (enabled || self.enabled.count_ones() > 1)
+    }
+}
+
+/// Drives the input select lines: the original I2S source relay plus two
+/// mux lines for boards with more than two inputs.
+pub struct InputSelect {
+    pins: [Output<'static>; 3],
+}
+
+impl InputSelect {
+    pub fn new(pins: InputSelectPins, pattern: u8) -> Self {
+        let level = |bit: u8| Level::from(pattern & (1 << bit) != 0);
+        InputSelect {
+            pins: [
+                Output::new(pins.pin6_sel0, level(0)),
+                Output::new(pins.pin27_sel1, level(1)),
+                Output::new(pins.pin28_sel2, level(2)),
+            ],
+        }
+    }
+
+    pub fn apply(&mut self, pattern: u8) {
+        for (bit, pin) in self.pins.iter_mut().enumerate() {
+            pin.set_level(Level::from(pattern & (1 << bit) != 0));
+        }
+    }
+}
@@ -1,15 +1,112 @@
-use defmt::info;
+use core::cell::RefCell;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use defmt::debug;
+use embassy_sync::blocking_mutex::Mutex;
 use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Sender};
 use embassy_time::Instant;
+use heapless::Vec;
 
-use infrared::{protocol::Nec, Receiver};
+use infrared::{
+    protocol::{nec::NecCommand, Nec},
+    Receiver,
+};
 
+use crate::browse::BROWSING;
+use crate::hid::HOST_VOLUME;
+use crate::hostlog::host_log;
 use crate::Command;
 use embassy_rp::{
     gpio::{Input, Pull},
     peripherals::PIN_3,
     Peri,
 };
+/// Learn mode: every received code goes to the host and none is acted on
+/// locally, so a mapping UI can capture the buttons it wants. Holds the
+/// uptime second learn mode ends at, 0 when off.
+static LEARN_UNTIL: AtomicU32 = AtomicU32::new(0);
+
+/// A mapping UI that goes away without switching learn mode off must not
+/// leave the remote dead; it re-sends `IrLearn(true)` to stay in it longer.
+const LEARN_TIMEOUT_SECS: u32 = 60;
+
+const MAX_FORWARDED: usize = 16;
+
+/// (address, command) pairs the host took over from the local mapping.
+static FORWARDED: Mutex<CriticalSectionRawMutex, RefCell<Vec<(u8, u8), MAX_FORWARDED>>> =
+    Mutex::new(RefCell::new(Vec::new()));
+
+fn uptime_secs() -> u32 {
+    Instant::now().as_secs() as u32
+}
+
+pub fn set_learn(on: bool) {
+    let until = if on {
+        uptime_secs() + LEARN_TIMEOUT_SECS
+    } else {
+        0
+    };
+    LEARN_UNTIL.store(until, Ordering::Relaxed);
+}
+
+fn learning() -> bool {
+    match LEARN_UNTIL.load(Ordering::Relaxed) {
+        0 => false,
+        until if uptime_secs() < until => true,
+        _ => {
+            host_log!(Info, "IR learn mode timed out");
+            LEARN_UNTIL.store(0, Ordering::Relaxed);
+            false
+        }
+    }
+}
+
+/// Adds or removes a code from the forward list; false if the list is full.
+pub fn set_forwarded(address: u8, command: u8, forward: bool) -> bool {
+    FORWARDED.lock(|f| {
+        let mut f = f.borrow_mut();
+        let pos = f.iter().position(|&c| c == (address, command));
+        match (pos, forward) {
+            (Some(i), false) => {
+                f.swap_remove(i);
+                true
+            }
+            (None, true) => f.push((address, command)).is_ok(),
+            _ => true,
+        }
+    })
+}
+
+pub fn clear_forwarded() {
+    FORWARDED.lock(|f| f.borrow_mut().clear());
+}
+
+/// Learn mode and the forward list belong to the host session that set
+/// them; a new or vanished host gets the remote back as shipped.
+pub fn release_host() {
+    set_learn(false);
+    clear_forwarded();
+}
+
+fn is_forwarded(cmd: &NecCommand) -> bool {
+    FORWARDED.lock(|f| f.borrow().contains(&(cmd.addr, cmd.cmd)))
+}
+
+/// Never blocks: a stalled host link must not hold up decoding of the
+/// locally handled buttons.
+fn forward(control: &Sender<'static, CriticalSectionRawMutex, Command, 64>, cmd: &NecCommand) {
+    if control
+        .try_send(Command::ForwardIr {
+            address: cmd.addr,
+            command: cmd.cmd,
+            repeat: cmd.repeat,
+        })
+        .is_err()
+    {
+        host_log!(Warn, "Command queue full, IR code not forwarded");
+    }
+}
+
 #[embassy_executor::task]
 pub async fn listen_ir_receiver(
     control: Sender<'static, CriticalSectionRawMutex, Command, 64>,
@@ -34,10 +131,16 @@ pub async fn listen_ir_receiver(
         // press. A huge dt just resets the decoder state machine.
         let dt: u32 = dur.as_micros().try_into().unwrap_or(u32::MAX);
         if let Ok(Some(cmd)) = ir_recv.event(dt, !rising) {
-            info!("cmd: {}, addr: {}, rep: {}", cmd.cmd, cmd.addr, cmd.repeat);
-            if cmd.addr != 128 {
+            debug!("cmd: {}, addr: {}, rep: {}", cmd.cmd, cmd.addr, cmd.repeat);
+            // Codes for other remotes, or ones the host asked for, are the
+            // host's to handle.
+            if learning() || cmd.addr != 128 || is_forwarded(&cmd) {
+                forward(&control, &cmd);
+                lastedge = now;
                 continue;
             }
+            // Arrows, OK and return navigate an open browser.
+            let browsing = BROWSING.load(Ordering::Relaxed);
             match cmd.cmd {
                 38 | 40 => {
                     let fire = if cmd.repeat {
@@ -49,30 +152,54 @@ pub async fn listen_ir_receiver(
                     };
                     if fire {
                         last_vol_step = now;
+                        let up = cmd.cmd == 38;
                         control
-                            .send(if cmd.cmd == 38 { Command::VolumeUp } else { Command::VolumeDown })
+                            .send(match (browsing, up) {
+                                (true, true) => Command::BrowseStep(-1),
+                                (true, false) => Command::BrowseStep(1),
+                                (false, true) => Command::VolumeUp,
+                                (false, false) => Command::VolumeDown,
+                            })
                             .await;
                     }
                 }
                 39 => {
                     if !cmd.repeat {
-                        control.send(Command::Next).await
+                        control
+                            .send(if browsing {
+                                Command::BrowseEnqueue
+                            } else {
+                                Command::Next
+                            })
+                            .await
                     }
                 }
                 37 => {
                     if !cmd.repeat {
-                        control.send(Command::Prev).await
+                        control
+                            .send(if browsing {
+                                Command::BrowseBack
+                            } else {
+                                Command::Prev
+                            })
+                            .await
                     }
                 }
                 13 => {
                     if !cmd.repeat {
-                        control.send(Command::TogglePlay).await
+                        control
+                            .send(if browsing {
+                                Command::BrowseSelect
+                            } else {
+                                Command::TogglePlay
+                            })
+                            .await
                     }
                 }
                 // menu button
                 73 => {
                     if !cmd.repeat {
-                        control.send(Command::ToggleInput).await
+                        control.send(Command::NextInput).await
                     }
                 }
                 // mouse button
@@ -84,7 +211,13 @@ pub async fn listen_ir_receiver(
                 // return button
                 27 => {
                     if !cmd.repeat {
-                        control.send(Command::NextDacSoundSetting).await
+                        control
+                            .send(if browsing {
+                                Command::BrowseBack
+                            } else {
+                                Command::NextDacSoundSetting
+                            })
+                            .await
                     }
                 }
                 // power button
@@ -117,6 +250,25 @@ pub async fn listen_ir_receiver(
                         control.send(Command::ToggleDisplayMode).await
                     }
                 }
+                // 5 button: same as holding the encoder button
+                53 => {
+                    if !cmd.repeat {
+                        control.send(Command::BrowseHold).await
+                    }
+                }
+                // 6 button: menu steps inputs forward, this steps back
+                54 => {
+                    if !cmd.repeat {
+                        control.send(Command::PrevInput).await
+                    }
+                }
+                // 0 button: mute, while the volume controls drive the host
+                // mixer; forwarded otherwise
+                48 if HOST_VOLUME.load(Ordering::Relaxed) => {
+                    if !cmd.repeat {
+                        control.send(Command::Mute).await
+                    }
+                }
                 // VOL+ button
                 78 => {
                     if !cmd.repeat {
@@ -134,7 +286,7 @@ pub async fn listen_ir_receiver(
                     }
                 }
 
-                _ => {}
+                _ => forward(&control, &cmd),
             }
         }
         lastedge = now;
@@ -0,0 +1,138 @@
+use heapless::{String, Vec};
+use rsplayer_wire::{LibraryEntry, LibraryLevel, LibraryQuery, QUEUE_PAGE};
+
+use crate::browse::PagedList;
+
+/// One list on the navigation stack: all artists, the albums of an artist
+/// or the tracks of an album.
+struct Level {
+    level: LibraryLevel,
+    /// Id of the entry opened to get here.
+    parent: Option<u32>,
+    list: PagedList<LibraryEntry>,
+}
+
+impl Level {
+    fn new(level: LibraryLevel, parent: Option<u32>) -> Self {
+        Level {
+            level,
+            parent,
+            list: PagedList::new(),
+        }
+    }
+
+    fn query(&self, offset: u32) -> LibraryQuery {
+        LibraryQuery {
+            level: self.level,
+            parent: self.parent,
+            offset,
+        }
+    }
+}
+
+/// Result of pressing on the selected entry.
+pub enum Selection {
+    /// A list was opened; its first page has to be requested.
+    Open(LibraryQuery),
+    /// A track to play.
+    Play(u32),
+}
+
+/// Artist → album → track navigation over the host's library. Lists left
+/// for a deeper one keep their page and cursor, so going back needs no
+/// request.
+pub struct LibraryBrowser {
+    stack: Vec<Level, 3>,
+}
+
+impl LibraryBrowser {
+    /// Starts at the artist list; its first page comes from
+    /// [`query`](Self::query).
+    pub fn new() -> Self {
+        let mut stack = Vec::new();
+        stack.push(Level::new(LibraryLevel::Artists, None)).ok();
+        LibraryBrowser { stack }
+    }
+
+    fn top(&self) -> &Level {
+        self.stack.last().unwrap()
+    }
+
+    fn top_mut(&mut self) -> &mut Level {
+        self.stack.last_mut().unwrap()
+    }
+
+    /// First page of the list shown.
+    pub fn query(&self) -> LibraryQuery {
+        self.top().query(0)
+    }
+
+    /// Takes a page from the host. Pages of a list no longer shown, e.g.
+    /// after going back before they arrived, are dropped.
+    pub fn page(&mut self, query: LibraryQuery, total: u32, items: Vec<LibraryEntry, QUEUE_PAGE>) {
+        let top = self.top_mut();
+        if (query.level, query.parent) != (top.level, top.parent) {
+            return;
+        }
+        top.list.page(query.offset, total, items, None);
+    }
+
+    /// See [`PagedList::step`].
+    pub fn step(&mut self, delta: i32) -> Option<LibraryQuery> {
+        let top = self.top_mut();
+        top.list.step(delta).map(|offset| top.query(offset))
+    }
+
+    pub fn select(&mut self) -> Option<Selection> {
+        let top = self.top();
+        let id = top.list.selected()?.id;
+        let child = match top.level {
+            LibraryLevel::Artists => LibraryLevel::Albums,
+            LibraryLevel::Albums => LibraryLevel::Tracks,
+            LibraryLevel::Tracks => return Some(Selection::Play(id)),
+        };
+        let level = Level::new(child, Some(id));
+        let query = level.query(0);
+        self.stack.push(level).ok()?;
+        Some(Selection::Open(query))
+    }
+
+    /// Level and id of the entry under the cursor, e.g. to enqueue it.
+    pub fn selected(&self) -> Option<(LibraryLevel, u32)> {
+        let top = self.top();
+        top.list.selected().map(|e| (top.level, e.id))
+    }
+
+    /// Returns to the previous list; false if there is none and the
+    /// browser should close.
+    pub fn back(&mut self) -> bool {
+        if self.stack.len() == 1 {
+            return false;
+        }
+        self.stack.pop();
+        true
+    }
+
+    pub fn title(&self) -> String<24> {
+        let top = self.top();
+        top.list.title(match top.level {
+            LibraryLevel::Artists => "Artists",
+            LibraryLevel::Albums => "Albums",
+            LibraryLevel::Tracks => "Tracks",
+        })
+    }
+
+    pub fn items(&self) -> &[LibraryEntry] {
+        self.top().list.items()
+    }
+
+    pub fn selected_row(&self) -> Option<usize> {
+        self.top().list.selected_row()
+    }
+}
+
+impl Default for LibraryBrowser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -5,17 +5,18 @@ use core::sync::atomic::AtomicBool;
 
 use assign_resources::assign_resources;
 
-use defmt::unwrap;
-use defmt::{debug, info};
+use defmt::{debug, info, unwrap};
 use display::{DisplayMode, OledDisplay};
 use embassy_rp::gpio::{Level, Output};
 use embassy_rp::peripherals::{self, I2C1, USB};
 use embassy_rp::pio::Pio;
 use embassy_rp::pio_programs::rotary_encoder::{PioEncoder, PioEncoderProgram};
 
+use embassy_futures::join::join;
 use embassy_futures::select::{select, Either};
 use embassy_rp::bind_interrupts;
 use embassy_rp::usb::{Driver, InterruptHandler};
+use embassy_rp::watchdog::Watchdog;
 use embassy_sync::mutex::Mutex;
 use embassy_time::{Instant, Timer};
 
@@ -26,38 +27,64 @@ use embassy_rp::Peri;
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_sync::channel::Channel;
 use embassy_usb::class::cdc_acm::{CdcAcmClass, State};
+use embassy_usb::class::hid::{HidWriter, State as HidState};
 use embassy_usb::UsbDevice;
 use heapless::String;
 use static_cell::StaticCell;
 
 use crate::amanero::Amanero;
+use crate::browse::Browser;
+use crate::hid::{HidMode, MediaKey};
+use crate::hostlog::host_log;
+use crate::input::{InputKind, InputName, InputPolicy, InputSelect, InputTable, MAX_INPUTS};
+use crate::library::{LibraryBrowser, Selection};
+use crate::queue::{QueueBrowser, QueueLabel};
 use crate::rsplayer::RsPlayer;
+use crate::setting::Change;
 use embassy_rp::peripherals::PIO0;
 
 use crate::dac::common::{Akm44xxDac, FilterType};
 use dac::common::SampleRate;
 
 pub use rsplayer_wire::PlaybackMode;
-use rsplayer_wire::FwPlayerCmd;
+use rsplayer_wire::{
+    DeviceState, DfuStatus, FwPlayerCmd, LogLevel, ResetKind, ResetReason, TrackExt, ART_CHUNK,
+    DFU_CHUNK, QUEUE_PAGE,
+};
+use rsplayer_wire::{
+    LibraryEntry, LibraryLevel, LibraryQuery, Severity, NOTIFY_LEN, SPECTRUM_MAX_BANDS,
+};
 // Only one of these will be active based on the feature flag
 #[cfg(feature = "debug")]
 use defmt_rtt as _;
 #[cfg(feature = "debug")]
 use panic_probe as _;
 
-#[cfg(feature = "release")]
-use panic_reset as _;
-
+mod art;
+mod browse;
 mod dac;
+mod dfu;
 mod display;
 
 mod amanero;
 mod flash;
+mod frame;
 // mod gpio;
+mod hid;
+mod hostlog;
 mod i2c_helper;
+mod input;
 mod ir;
+mod library;
+mod outbox;
+mod queue;
+mod rate_meter;
 mod rotary;
 mod rsplayer;
+mod setting;
+#[cfg(feature = "wm8805")]
+mod spdif;
+mod supervisor;
 mod usb;
 
 bind_interrupts!(struct IrqsI2c {
@@ -82,15 +109,24 @@ assign_resources! {
     out: OutputPins {
         pin0: PIN_0,
         pin1: PIN_1,
-        pin6: PIN_6,
         // pin7: PIN_7,
     }
-    dac: DacResources{
+    input_select: InputSelectPins {
+        pin6_sel0: PIN_6,
+        pin27_sel1: PIN_27,
+        pin28_sel2: PIN_28,
+    }
+    i2c_bus: I2cBusResources {
         i2c: I2C1,
         pin15_i2c_scl: PIN_15,
         pin14_i2c_sda: PIN_14,
+    }
+    dac: DacResources{
         pin2_dac_pdn: PIN_2,
     }
+    spdif: SpdifPins {
+        pin26_int: PIN_26,
+    }
     display: DisplayResources {
         spi0: SPI0,
         dmach3: DMA_CH3,
@@ -109,27 +145,84 @@ assign_resources! {
         pin17_b: PIN_17,
         pin21_sw: PIN_21,
     }
+    rate_meter: RateMeterPins {
+        pin12_lrck: PIN_12,
+        pin13_bclk: PIN_13,
+    }
 }
 
 static DISPLAY: Mutex<CriticalSectionRawMutex, Option<OledDisplay>> = Mutex::new(None);
 
+/// Detector that produced a sample rate reading. Only readings from the
+/// detector wired to the selected input are acted upon.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, defmt::Format)]
+enum RateSource {
+    /// Amanero rate pins (USB input).
+    Amanero,
+    /// PIO frequency counter on LRCK/BCLK (optical input).
+    Meter,
+    /// S/PDIF receiver chip (optical input).
+    #[cfg(feature = "wm8805")]
+    Spdif,
+}
+
+/// Detector trusted for S/PDIF inputs: the receiver chip when fitted,
+/// otherwise the PIO frequency counter.
+#[cfg(feature = "wm8805")]
+const SPDIF_RATE_SOURCE: RateSource = RateSource::Spdif;
+#[cfg(not(feature = "wm8805"))]
+const SPDIF_RATE_SOURCE: RateSource = RateSource::Meter;
+
+impl RateSource {
+    fn for_input(kind: InputKind) -> Self {
+        match kind {
+            InputKind::Usb => RateSource::Amanero,
+            InputKind::Spdif => SPDIF_RATE_SOURCE,
+            InputKind::I2s => RateSource::Meter,
+        }
+    }
+}
+
+/// Asks the detector of `kind` for a fresh reading after a switch.
+fn refresh_sample_rate(kind: InputKind) {
+    match kind {
+        InputKind::Usb => amanero::REFRESH_SAMPLE_RATE.signal(()),
+        _ => rate_meter::REFRESH_MEASURED_RATE.signal(()),
+    }
+}
+
 #[derive(Eq, PartialEq, Debug)]
 enum Command {
-    UpdateSampleRate(SampleRate),
+    UpdateSampleRate(RateSource, SampleRate),
+    #[cfg(feature = "wm8805")]
+    UpdateSpdifStatus(spdif::SpdifStatus),
     UpdateTrackInfo {
         title: String<64>,
         artist: String<64>,
         album: String<64>,
     },
+    UpdateTrackExt(TrackExt),
+    UpdateUpNext {
+        title: String<64>,
+        artist: String<64>,
+    },
     UpdateVU {
         left: u8,
         right: u8,
     },
+    /// Band magnitudes, low to high frequency, for the spectrum mode.
+    UpdateSpectrum(heapless::Vec<u8, SPECTRUM_MAX_BANDS>),
     UpdateProgress {
         current: String<16>,
         total: String<16>,
         percent: u8,
     },
+    /// Position to run the progress bar from until the next update.
+    UpdatePosition {
+        position_ms: u32,
+        duration_ms: u32,
+        playing: bool,
+    },
     UpdatePlaybackMode(PlaybackMode),
     TogglePower,
     PowerOn,
@@ -137,7 +230,19 @@ enum Command {
     VolumeUp,
     VolumeDown,
     SetVolume(u8),
-    ToggleInput,
+    NextInput,
+    PrevInput,
+    SelectInput(u8),
+    SetInputName {
+        index: u8,
+        name: InputName,
+    },
+    SetInputEnabled {
+        index: u8,
+        enabled: bool,
+    },
+    SetInputOrder(heapless::Vec<u8, MAX_INPUTS>),
+    SetInputPolicy(InputPolicy),
 
     Next,
     Prev,
@@ -146,23 +251,194 @@ enum Command {
     TogglePlay,
     NextDacSoundSetting,
     NextDacFilterType,
+    SetDacSoundSetting(u8),
+    SetDacFilterType(u8),
     ToggleDacDsdDclkPolarity,
     ToggleDacDsdCutoffFreqFilter,
     ToggleDacDsdDclksClock,
     QueryCurrentVolume,
     ToggleRandomPlay,
     ToggleDisplayMode,
-    /// USB host (re)connected — report power state and volume so the host
+    SetDisplayMode(u8),
+    /// USB host (re)connected — report the full device state so the host
     /// can resynchronize after a restart of either side.
     UsbConnected,
+    QueryState,
+    /// NEC code the local mapping does not handle, or learn mode is on.
+    ForwardIr {
+        address: u8,
+        command: u8,
+        repeat: bool,
+    },
+    SetIrLearn(bool),
+    SetIrForward {
+        address: u8,
+        command: u8,
+        forward: bool,
+    },
+    ClearIrForward,
+    DfuBegin {
+        size: u32,
+        crc32: u32,
+    },
+    DfuFinish,
+    DfuAbort,
+    SetHidMode(HidMode),
+    /// Mute key for the host mixer; only produced in `HidMode::HidVolume`.
+    Mute,
+    SetLogLevel(LogLevel),
+    /// First step of a host reset: answered with a token the host has to
+    /// echo in `ConfirmReset`.
+    RequestReset(ResetKind),
+    ConfirmReset {
+        kind: ResetKind,
+        token: u32,
+    },
+    /// Host introduced itself; answered with our own hello. An incompatible
+    /// host only gets the hello, its other commands are dropped in `usb`.
+    HostHello {
+        compatible: bool,
+    },
+    HostHeartbeat,
+    /// Grace period in seconds before an offline host is power-cycled,
+    /// 0 to never do it.
+    SetHostRecovery(u16),
+    /// Start of a cover image; replaces any transfer still running.
+    ArtBegin {
+        id: u16,
+        width: u16,
+        height: u16,
+        format: art::ArtFormat,
+    },
+    ArtCancel,
+    /// Held encoder button: opens the queue, then the library, then goes
+    /// back like `BrowseBack`.
+    BrowseHold,
+    BrowseStep(i8),
+    /// Plays the queue entry under the cursor; in the library, opens the
+    /// artist or album, or plays the track.
+    BrowseSelect,
+    /// Adds the library entry under the cursor to the queue.
+    BrowseEnqueue,
+    /// Up one library level, or out of the browser.
+    BrowseBack,
+    /// The payload waiting in `BULK_SLOT`.
+    Bulk,
+    /// Host event to show for `duration_ms`, 0 for the default.
+    Notify {
+        text: String<NOTIFY_LEN>,
+        severity: Severity,
+        duration_ms: u16,
+    },
+}
+
+impl Command {
+    /// Commands still handled while the system is powered off.
+    fn allowed_while_off(&self) -> bool {
+        matches!(
+            self,
+            Command::TogglePower
+                | Command::PowerOn
+                | Command::UsbConnected
+                | Command::HostHello { .. }
+                | Command::QueryState
+                | Command::DfuBegin { .. }
+                | Command::Bulk
+                | Command::DfuFinish
+                | Command::DfuAbort
+                | Command::RequestReset(_)
+                | Command::ConfirmReset { .. }
+                | Command::SetLogLevel(_)
+                | Command::HostHeartbeat
+        )
+    }
+}
+
+/// Host payloads too large to give each of the 64 `CMD_CHANNEL` slots room
+/// for. The USB task parks one in `BULK_SLOT` and then queues
+/// `Command::Bulk`, so it is handled in order with the commands around it.
+#[derive(Eq, PartialEq, Debug)]
+enum Bulk {
+    DfuChunk {
+        offset: u32,
+        data: heapless::Vec<u8, DFU_CHUNK>,
+    },
+    ArtChunk {
+        id: u16,
+        offset: u32,
+        data: heapless::Vec<u8, ART_CHUNK>,
+    },
+    UpdateQueuePage {
+        offset: u32,
+        total: u32,
+        current: Option<u32>,
+        items: heapless::Vec<QueueLabel, QUEUE_PAGE>,
+    },
+    UpdateLibraryPage {
+        query: LibraryQuery,
+        total: u32,
+        items: heapless::Vec<LibraryEntry, QUEUE_PAGE>,
+    },
+}
+
+impl Bulk {
+    fn allowed_while_off(&self) -> bool {
+        matches!(self, Bulk::DfuChunk { .. })
+    }
 }
 
 static CMD_CHANNEL: Channel<CriticalSectionRawMutex, Command, 64> = Channel::new();
+/// Single slot: the USB task waits for the previous payload to be taken
+/// before parking the next one.
+static BULK_SLOT: Channel<CriticalSectionRawMutex, Bulk, 1> = Channel::new();
 static mut CORE1_STACK: Stack<8192> = Stack::new();
 static EXECUTOR0: StaticCell<Executor> = StaticCell::new();
 static EXECUTOR1: StaticCell<Executor> = StaticCell::new();
 static POWER_ON: AtomicBool = AtomicBool::new(false);
 
+/// Uptime after which a freshly updated image marks itself good; a crash
+/// or hang before then reverts to the previous image on the next reset.
+const DFU_CONFIRM_SECS: u64 = 30;
+/// How long a reset token stays valid for the host's confirmation.
+const RESET_CONFIRM_SECS: u64 = 5;
+/// How long USB must stay silent before auto policies fall back to a locked
+/// S/PDIF input.
+const AUTO_FALLBACK_SECS: u64 = 10;
+/// Firmware heartbeat period. Kept under the 5s idle tick of the command
+/// loop so an idle loop still sends one on every tick.
+const HEARTBEAT_SECS: u64 = 4;
+/// Host heartbeats missing for this long mark the host offline.
+const HOST_TIMEOUT_SECS: u64 = 15;
+/// PSU off time when power-cycling a hung host.
+const HOST_POWER_CYCLE_SECS: u64 = 10;
+/// Browsers close by themselves after this long without input.
+const BROWSE_IDLE_SECS: u64 = 30;
+
+/// Closes the browser, if open, and has the screen under it repainted by
+/// re-entering the current display mode.
+fn close_browser(browser: &mut Option<Browser>, display_mode: DisplayMode) {
+    if browser.take().is_some() {
+        browse::BROWSING.store(false, core::sync::atomic::Ordering::Relaxed);
+        CMD_CHANNEL
+            .try_send(Command::SetDisplayMode(display_mode as u8))
+            .ok();
+    }
+}
+
+fn draw_browser(disp: &mut OledDisplay, browser: &Browser) {
+    match browser {
+        Browser::Queue(q) => {
+            let (selected, playing) = q.rows();
+            let rows = q.items().iter().map(|l| l.as_str());
+            disp.draw_list(&q.title(), rows, selected, playing);
+        }
+        Browser::Library(l) => {
+            let rows = l.items().iter().map(|e| e.label.as_str());
+            disp.draw_list(&l.title(), rows, l.selected_row(), None);
+        }
+    }
+}
+
 #[cortex_m_rt::entry]
 fn main() -> ! {
     let php = embassy_rp::init(Default::default());
@@ -174,12 +450,22 @@ fn main() -> ! {
     // block_for(Duration::from_millis(150));
 
     let res = split_resources!(php);
-    let dac = Akm44xxDac::new(res.dac);
+    let i2c_bus = i2c_helper::init_bus(res.i2c_bus);
+    let dac = Akm44xxDac::new(res.dac, i2c_bus);
+    #[cfg(feature = "wm8805")]
+    let spdif_receiver = spdif::Wm8805::new(i2c_bus, res.spdif);
     let amanero = Amanero::new(res.amanero);
-    let flash = flash::Storage::new(res.flash);
+    let mut flash = flash::Storage::new(res.flash);
+    let dfu = dfu::Dfu::new(flash.shared());
+    let watchdog = Watchdog::new(php.WATCHDOG);
+    let reset_reason = supervisor::take_reset_reason(&watchdog);
 
     let Pio {
-        mut common, sm0, ..
+        mut common,
+        sm0,
+        sm1,
+        sm2,
+        ..
     } = Pio::new(php.PIO0, IrqsPio);
 
     let prg = PioEncoderProgram::new(&mut common);
@@ -191,6 +477,21 @@ fn main() -> ! {
         &prg,
     );
 
+    let counter_prg = rate_meter::PioEdgeCounterProgram::new(&mut common);
+    let lrck = rate_meter::PioEdgeCounter::new(
+        &mut common,
+        sm1,
+        res.rate_meter.pin12_lrck,
+        &counter_prg,
+    );
+    let bclk = rate_meter::PioEdgeCounter::new(
+        &mut common,
+        sm2,
+        res.rate_meter.pin13_bclk,
+        &counter_prg,
+    );
+    let rate_meter = rate_meter::RateMeter::new(lrck, bclk);
+
     // USB
     // Create the driver, from the HAL.
     let usb_driver = Driver::new(php.USB, IrqsUsb);
@@ -232,7 +533,13 @@ fn main() -> ! {
         CdcAcmClass::new(&mut usb_builder, state, 64)
     };
     let (usb_tx, usb_rx) = usb_class.split();
-    let rsplayer = RsPlayer::new(usb_tx);
+    let media_keys = {
+        static STATE: StaticCell<HidState> = StaticCell::new();
+        let state = STATE.init(HidState::new());
+        hid::MediaKeys::new(HidWriter::new(&mut usb_builder, state, hid::config()))
+    };
+    let hid_mode = HidMode::from(flash.load_hid_mode());
+    let rsplayer = RsPlayer::new(hid_mode);
     // Build the builder.
     let usb_device = usb_builder.build();
 
@@ -253,6 +560,15 @@ fn main() -> ! {
                 )));
                 unwrap!(spawner.spawn(ir::listen_ir_receiver(CMD_CHANNEL.sender(), php.PIN_3)));
                 unwrap!(spawner.spawn(amanero::listen_pin_changes(CMD_CHANNEL.sender(), amanero)));
+                unwrap!(spawner.spawn(rate_meter::listen_measured_rate(
+                    CMD_CHANNEL.sender(),
+                    rate_meter
+                )));
+                #[cfg(feature = "wm8805")]
+                unwrap!(spawner.spawn(spdif::listen_spdif_receiver(
+                    CMD_CHANNEL.sender(),
+                    spdif_receiver
+                )));
             });
         },
     );
@@ -261,8 +577,19 @@ fn main() -> ! {
     let executor0 = EXECUTOR0.init(Executor::new());
     executor0.run(|spawner| {
         unwrap!(spawner.spawn(tick_display()));
-        unwrap!(spawner.spawn(process_commands(dac, rsplayer, res.out, res.display, flash)));
+        unwrap!(spawner.spawn(process_commands(
+            dac,
+            rsplayer,
+            res.out,
+            res.input_select,
+            res.display,
+            flash,
+            dfu,
+            reset_reason
+        )));
+        unwrap!(spawner.spawn(supervisor::supervise(watchdog)));
         unwrap!(spawner.spawn(usb_task(usb_device)));
+        unwrap!(spawner.spawn(rsplayer::usb_tx(usb_tx, media_keys)));
         unwrap!(spawner.spawn(usb::listen_usb_commands(CMD_CHANNEL.sender(), usb_rx)));
     });
 }
@@ -272,7 +599,35 @@ type MyUsbDevice = UsbDevice<'static, MyUsbDriver>;
 
 #[embassy_executor::task]
 async fn usb_task(mut usb: MyUsbDevice) {
-    usb.run().await
+    // Polled by the same task, so check-ins stop if `run` ever blocks.
+    let check_in = async {
+        loop {
+            supervisor::check_in(supervisor::Task::Usb);
+            Timer::after_secs(1).await;
+        }
+    };
+    join(usb.run(), check_in).await;
+}
+
+/// Leaves the hardware safe for a reset: pending settings flushed, output
+/// muted and the DAC held in power-down, so nothing pops while the board
+/// restarts.
+async fn prepare_reset(
+    flash: &mut flash::Storage,
+    pending_volume: Option<u8>,
+    mute_out_relay: &mut Output<'static>,
+    dac: &mut Akm44xxDac,
+) {
+    if let Some(vol) = pending_volume {
+        flash.save_volume(vol);
+    }
+    mute_out_relay.set_low();
+    Timer::after_millis(50).await;
+    dac.pdn_pin.set_low();
+    if let Some(disp) = DISPLAY.lock().await.as_mut() {
+        disp.draw_powered_off();
+    }
+    Timer::after_millis(200).await;
 }
 
 #[embassy_executor::task]
@@ -291,6 +646,7 @@ pub async fn dim_display() {
 #[embassy_executor::task]
 pub async fn tick_display() {
     loop {
+        supervisor::check_in(supervisor::Task::Display);
         let is_power_on = POWER_ON.load(core::sync::atomic::Ordering::Relaxed);
         if is_power_on {
             if let Some(disp) = DISPLAY.lock().await.as_mut() {
@@ -310,12 +666,14 @@ pub async fn process_commands(
     mut dac: Akm44xxDac,
     mut rsplayer: RsPlayer,
     out_resources: OutputPins,
+    input_select_resources: InputSelectPins,
     display_resources: DisplayResources,
     mut flash: flash::Storage,
+    mut dfu: dfu::Dfu,
+    reset_reason: ResetReason,
 ) {
     let mut pwr_psu_relay = Output::new(out_resources.pin1, Level::Low);
     let mut mute_out_relay = Output::new(out_resources.pin0, Level::Low);
-    let mut i2s_signal_select = Output::new(out_resources.pin6, Level::High);
     let mut last_sample_rate = None;
 
     // Backup resources for re-initialization if needed
@@ -329,6 +687,9 @@ pub async fn process_commands(
 
     if let Some(disp) = DISPLAY.lock().await.as_mut() {
         disp.draw_powered_off();
+        if reset_reason != ResetReason::PowerOn {
+            disp.draw_reset_reason(supervisor::describe(reset_reason));
+        }
     }
     mute_out_relay.set_low();
     let mut current_volume = flash.load_volume();
@@ -349,22 +710,70 @@ pub async fn process_commands(
     // Input and filter are cached in RAM and updated at their change points.
     // Reading them from flash on every command copied two 4 KB sectors per
     // loop pass — at 20 Hz VU traffic that was constant needless work.
+    let mut inputs = flash.load_input_table().unwrap_or_default();
+    inputs.publish();
     let mut input = flash.load_input();
-    let mut current_input = if input == 0 { "OPT" } else { "USB" };
-    let mut filter_val = flash.load_filter_type();
+    // A loaded table always has an enabled input, so this lands in range
+    // even when the stored index is stale.
+    if !inputs.is_enabled(input) {
+        input = inputs.first_enabled();
+    }
+    let mut input_def = unwrap!(inputs.get(input)).clone();
+    // Track info and progress only make sense while rsplayer is the source.
+    let mut on_player_input = input_def.kind == InputKind::Usb;
+    let mut input_select = InputSelect::new(input_select_resources, input_def.select);
+    // Older firmware cycled one past the last filter; such values restart
+    // at the default.
+    let mut filter_val = dac::FILTER.or_default(flash.load_filter_type());
     let mut current_filter = FilterType::from(filter_val).as_str();
 
+    // Signal activity seen on inputs other than the selected one, for the
+    // auto input policies. The Amanero pins and the S/PDIF receiver report
+    // independently of the select relays.
+    let mut input_policy = InputPolicy::from(flash.load_input_policy());
+    if !input_policy.supported() {
+        input_policy = InputPolicy::Manual;
+    }
+    let mut usb_signal = false;
+    // Only the receiver chip can see a stream on a deselected S/PDIF input.
+    #[cfg_attr(not(feature = "wm8805"), allow(unused_mut))]
+    let mut spdif_signal = false;
+
     // Cooldown after a power transition: one physical IR press can produce
     // several non-repeat NEC frames (signal dropout mid-hold restarts the
     // frame), and presses queued while the 1s power-on sequence runs would
     // otherwise toggle the system right back.
     let mut last_power_transition: Option<Instant> = None;
 
+    // Last DAC I2C state reported to the host.
+    let mut dac_fault = false;
+    // An updated image reverts on the next reset unless it confirms itself;
+    // running the command loop for a while is taken as proof it works.
+    let mut dfu_confirmed = false;
+    // Resets need a second, matching message from the host.
+    let mut pending_reset: Option<(ResetKind, u32, Instant)> = None;
+
+    // Host liveness. Only a host that has sent a heartbeat is watched, so
+    // hosts predating heartbeats are never reported offline or power-cycled.
+    let mut last_heartbeat_sent = Instant::now();
+    let mut last_host_heartbeat: Option<Instant> = None;
+    let mut host_offline = false;
+    let mut host_recovery_secs = flash.load_host_recovery();
+    // Set while the PSU is off for a host power cycle.
+    let mut host_power_cycle: Option<Instant> = None;
+    // Cover image being streamed to the panel.
+    let mut art: Option<art::ArtTransfer> = None;
+    let mut current_track: Option<(String<64>, String<64>, String<64>)> = None;
+    // Queue or library list covering the screen, and its last input.
+    let mut browser: Option<Browser> = None;
+    let mut browse_input = Instant::now();
+
     loop {
         let cmd_future = CMD_CHANNEL.receive();
         let timeout_future = Timer::after_secs(5);
 
         let selected = select(cmd_future, timeout_future).await;
+        supervisor::check_in(supervisor::Task::Commands);
 
         if let Some(since) = volume_dirty_since {
             if since.elapsed().as_secs() >= 2 {
@@ -374,6 +783,88 @@ pub async fn process_commands(
             }
         }
 
+        // Auto fallback: USB has been silent long enough and the receiver has
+        // a playable stream. Routed through `SelectInput` so it takes the
+        // same mute/relay/display path as a manual switch.
+        if input_policy != InputPolicy::Manual
+            && on_player_input
+            && spdif_signal
+            && silence_start_time.is_some_and(|t| t.elapsed().as_secs() >= AUTO_FALLBACK_SECS)
+        {
+            if let Some(id) = inputs.first_of_kind(InputKind::Spdif) {
+                host_log!(Info, "USB silent, falling back to input {}", id);
+                silence_start_time = None;
+                CMD_CHANNEL.try_send(Command::SelectInput(id)).ok();
+            }
+        }
+
+        // The DAC is unpowered during a host power cycle; not a fault.
+        if POWER_ON.load(core::sync::atomic::Ordering::Relaxed)
+            && host_power_cycle.is_none()
+            && dac.is_faulted() != dac_fault
+        {
+            dac_fault = !dac_fault;
+            host_log!(Warn, "DAC fault: {}", dac_fault);
+            rsplayer.send_dac_fault(dac_fault);
+        }
+
+        if last_heartbeat_sent.elapsed().as_secs() >= HEARTBEAT_SECS {
+            last_heartbeat_sent = Instant::now();
+            rsplayer.send_heartbeat(Instant::now().as_millis() as u32);
+        }
+
+        let host_silent_secs = last_host_heartbeat.map(|t| t.elapsed().as_secs());
+        if !host_offline
+            && POWER_ON.load(core::sync::atomic::Ordering::Relaxed)
+            && host_silent_secs.is_some_and(|s| s >= HOST_TIMEOUT_SECS)
+        {
+            host_offline = true;
+            host_log!(Warn, "Host heartbeat lost");
+            if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                disp.draw_host_status(false);
+            }
+        }
+        // Only the PSU relay is cycled; the system stays on as far as the
+        // user and the host are concerned. Powering off cancels the cycle.
+        if host_offline
+            && host_recovery_secs != 0
+            && POWER_ON.load(core::sync::atomic::Ordering::Relaxed)
+            && host_silent_secs.is_some_and(|s| s >= HOST_TIMEOUT_SECS + host_recovery_secs as u64)
+        {
+            host_log!(Warn, "Host unresponsive, power-cycling");
+            // Watching restarts with the first heartbeat after the reboot.
+            last_host_heartbeat = None;
+            host_offline = false;
+            if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                disp.draw_host_status(true);
+            }
+            mute_out_relay.set_low();
+            last_sample_rate = None;
+            pwr_psu_relay.set_low();
+            host_power_cycle = Some(Instant::now());
+        }
+        if host_power_cycle.is_some_and(|t| t.elapsed().as_secs() >= HOST_POWER_CYCLE_SECS) {
+            host_power_cycle = None;
+            host_log!(Info, "Host power restored");
+            pwr_psu_relay.set_high();
+            Timer::after_millis(1000).await;
+            // Same bring-up as power-on; the rate path unmutes once the
+            // input reports a rate again.
+            dac.initialize(filter_val, dac.sound_setting).await;
+            dac.set_volume(current_volume).await;
+            refresh_sample_rate(input_def.kind);
+        }
+
+        if browser.is_some() && browse_input.elapsed().as_secs() >= BROWSE_IDLE_SECS {
+            debug!("Browser idle, closing");
+            close_browser(&mut browser, display_mode);
+        }
+
+        if !dfu_confirmed && Instant::now().as_secs() >= DFU_CONFIRM_SECS {
+            dfu.confirm();
+            dfu_confirmed = true;
+        }
+
         let cmd = match selected {
             Either::First(c) => c,
             Either::Second(_) => {
@@ -391,21 +882,33 @@ pub async fn process_commands(
         };
 
         let is_power_on = POWER_ON.load(core::sync::atomic::Ordering::SeqCst);
-        if cmd != Command::TogglePower && cmd != Command::PowerOn && cmd != Command::UsbConnected && !is_power_on {
+        if !cmd.allowed_while_off() && !is_power_on {
             info!("Power is off, ignoring command");
             continue;
         }
         match cmd {
-            Command::ToggleDisplayMode => {
-                display_mode = match display_mode {
-                    DisplayMode::Normal => DisplayMode::VuMeter,
-                    DisplayMode::VuMeter => DisplayMode::BigInfo,
-                    DisplayMode::BigInfo => DisplayMode::Normal,
+            c @ (Command::ToggleDisplayMode | Command::SetDisplayMode(_)) => {
+                let previous_mode = display_mode;
+                let change = match c {
+                    Command::SetDisplayMode(val) => Change::Set(val),
+                    _ => Change::Next,
+                };
+                let Some(val) = setting::DISPLAY_MODE.resolve(display_mode as u8, change) else {
+                    info!("Unknown display mode: {}", change);
+                    continue;
                 };
-                flash.save_display_mode(display_mode as u8);
+                display_mode = DisplayMode::from(val);
+                // Also how a closed browser gets the screen back.
+                if display_mode != previous_mode {
+                    flash.save_display_mode(display_mode as u8);
+                }
+                if browser.take().is_some() {
+                    browse::BROWSING.store(false, core::sync::atomic::Ordering::Relaxed);
+                }
 
                 let mut disp_lock = DISPLAY.lock().await;
                 let d = disp_lock.as_mut().unwrap();
+                d.close_list();
                 d.set_display_mode(display_mode);
 
                 d.draw_background();
@@ -413,9 +916,9 @@ pub async fn process_commands(
 
                 match display_mode {
                     DisplayMode::Normal => {
-                        d.draw_header_status(current_input, current_filter);
+                        d.draw_header_status(&input_def.name, current_filter);
                         d.redraw_footer();
-                        if current_input == "OPT" {
+                        if !on_player_input {
                             d.draw_large_volume(current_volume);
                         } else {
                             d.draw_volume(current_volume);
@@ -432,6 +935,22 @@ pub async fn process_commands(
                         d.draw_playback_mode(current_playback_mode);
                         d.redraw_footer();
                     }
+                    DisplayMode::ArtInfo => {
+                        d.draw_header_status(&input_def.name, current_filter);
+                        d.draw_volume(current_volume);
+                        d.redraw_footer();
+                        if on_player_input {
+                            d.redraw_track_info();
+                            // Covers are not kept; ask for the current one.
+                            art = None;
+                            rsplayer.send_art_request();
+                        }
+                    }
+                    DisplayMode::Spectrum => {
+                        d.draw_header_status(&input_def.name, current_filter);
+                        d.draw_volume(current_volume);
+                        d.redraw_footer();
+                    }
                 }
             }
             c @ (Command::TogglePower | Command::PowerOn | Command::PowerOff) => {
@@ -450,35 +969,36 @@ pub async fn process_commands(
                 };
                 if should_turn_on && !is_power_on {
                     last_power_transition = Some(Instant::now());
+                    // The host boots with the PSU; watch it again once it
+                    // sends its first heartbeat.
+                    last_host_heartbeat = None;
+                    host_offline = false;
                     pwr_psu_relay.set_high();
                     Timer::after_millis(1000).await;
 
                     POWER_ON.store(true, core::sync::atomic::Ordering::Relaxed);
+                    host_log!(Info, "Powered on");
                     let mut disp_lock = DISPLAY.lock().await;
                     let disp = disp_lock.as_mut().unwrap();
 
-                    let stored_sound = flash.load_sound_setting();
+                    let stored_sound = dac::SOUND_SETTING.or_default(flash.load_sound_setting());
                     let stored_volume = flash.load_volume();
                     current_volume = stored_volume;
                     dac.initialize(filter_val, stored_sound).await;
                     dac.set_volume(stored_volume).await;
                     debug!("Stored input: {}", input);
-                    if input == 0 {
-                        i2s_signal_select.set_low();
-                        dac.dsd_pcm(SampleRate::Pcm441).await;
-                    } else {
-                        i2s_signal_select.set_high();
-                        amanero::REFRESH_SAMPLE_RATE.signal(());
-                    }
+                    input_select.apply(input_def.select);
+                    refresh_sample_rate(input_def.kind);
                     disp.turn_on_backlight();
+                    disp.draw_host_status(true);
                     disp.draw_background();
                     disp.draw_layout_lines();
-                    disp.draw_header_status(current_input, current_filter);
+                    disp.draw_header_status(&input_def.name, current_filter);
                     disp.draw_playback_mode(current_playback_mode);
                     disp.draw_volume(stored_volume);
                     match display_mode {
                         DisplayMode::Normal => {
-                            if input == 0 {
+                            if !on_player_input {
                                 disp.draw_large_volume(stored_volume);
                             } else {
                                 disp.redraw_track_info();
@@ -492,14 +1012,27 @@ pub async fn process_commands(
                             disp.redraw_track_info();
                             disp.redraw_footer();
                         }
+                        DisplayMode::ArtInfo => {
+                            if on_player_input {
+                                disp.redraw_track_info();
+                                rsplayer.send_art_request();
+                            }
+                        }
+                        DisplayMode::Spectrum => {}
                     }
                     disp.draw_footer("", "", "");
-                    rsplayer.send_power_state(true).await;
-                    // rsplayer.send_command("QueryCurrentPlayerInfo").await;
+                    rsplayer.send_power_state(true);
+                    // rsplayer.send_command("QueryCurrentPlayerInfo");
                 } else if !should_turn_on && is_power_on {
                     last_power_transition = Some(Instant::now());
+                    // Powering off ends a host power cycle; the PSU stays off.
+                    host_power_cycle = None;
                     POWER_ON.store(false, core::sync::atomic::Ordering::SeqCst);
-                    debug!("Powering off");
+                    host_log!(Info, "Powering off");
+                    // The standby screen replaces the browser.
+                    if browser.take().is_some() {
+                        browse::BROWSING.store(false, core::sync::atomic::Ordering::Relaxed);
+                    }
                     // Flush a pending deferred volume save before going dark.
                     if volume_dirty_since.take().is_some() {
                         flash.save_volume(current_volume);
@@ -513,12 +1046,21 @@ pub async fn process_commands(
 
                     pwr_psu_relay.set_low();
                     last_sample_rate = None;
-                    rsplayer.send_power_state(false).await;
+                    // The receiver re-reports its lock after power-on.
+                    #[cfg(feature = "wm8805")]
+                    {
+                        spdif_signal = false;
+                    }
+                    rsplayer.send_power_state(false);
                     debug!("Powered off");
                 }
             }
             Command::VolumeUp => {
-                info!("got VolumeUp");
+                debug!("got VolumeUp");
+                if rsplayer.hid_mode().host_volume() {
+                    rsplayer.send_volume_key(MediaKey::VolumeUp);
+                    continue;
+                }
                 let new_val = dac.volume_up().await;
                 volume_dirty_since = Some(Instant::now());
                 current_volume = new_val;
@@ -526,15 +1068,19 @@ pub async fn process_commands(
                     let mut d = DISPLAY.lock().await;
                     if let Some(disp) = d.as_mut() {
                         disp.draw_volume(new_val);
-                        if current_input == "OPT" && display_mode == DisplayMode::Normal {
+                        if !on_player_input && display_mode == DisplayMode::Normal {
                             disp.draw_large_volume(new_val);
                         }
                     }
                 }
-                rsplayer.send_current_volume(new_val).await;
+                rsplayer.send_current_volume(new_val);
             }
             Command::VolumeDown => {
-                info!("got VolumeDown");
+                debug!("got VolumeDown");
+                if rsplayer.hid_mode().host_volume() {
+                    rsplayer.send_volume_key(MediaKey::VolumeDown);
+                    continue;
+                }
                 let new_val = dac.volume_down().await;
                 volume_dirty_since = Some(Instant::now());
                 current_volume = new_val;
@@ -542,15 +1088,15 @@ pub async fn process_commands(
                     let mut d = DISPLAY.lock().await;
                     if let Some(disp) = d.as_mut() {
                         disp.draw_volume(new_val);
-                        if current_input == "OPT" && display_mode == DisplayMode::Normal {
+                        if !on_player_input && display_mode == DisplayMode::Normal {
                             disp.draw_large_volume(new_val);
                         }
                     }
                 }
-                rsplayer.send_current_volume(new_val).await;
+                rsplayer.send_current_volume(new_val);
             }
             Command::SetVolume(vol) => {
-                info!("Received SetVolume({})", vol);
+                debug!("Received SetVolume({})", vol);
                 dac.set_volume(vol).await;
                 volume_dirty_since = Some(Instant::now());
                 current_volume = vol;
@@ -558,7 +1104,7 @@ pub async fn process_commands(
                     let mut d = DISPLAY.lock().await;
                     if let Some(disp) = d.as_mut() {
                         disp.draw_volume(vol);
-                        if current_input == "OPT" && display_mode == DisplayMode::Normal {
+                        if !on_player_input && display_mode == DisplayMode::Normal {
                             disp.draw_large_volume(vol);
                         }
                     }
@@ -566,100 +1112,398 @@ pub async fn process_commands(
             }
             Command::ToggleRandomPlay => {
                 info!("got CyclePlaybackMode");
-                rsplayer.send_player(FwPlayerCmd::CyclePlaybackMode).await;
+                rsplayer.send_player(FwPlayerCmd::CyclePlaybackMode);
             }
-            Command::ToggleInput => {
+            c @ (Command::NextInput | Command::PrevInput | Command::SelectInput(_)) => {
+                let target = match c {
+                    Command::NextInput => inputs.next(input),
+                    Command::PrevInput => inputs.prev(input),
+                    Command::SelectInput(id) => id,
+                    _ => unreachable!(),
+                };
+                if target == input {
+                    continue;
+                }
+                let Some(def) = inputs.get(target).filter(|d| d.enabled).cloned() else {
+                    host_log!(Warn, "Input {} not available", target);
+                    continue;
+                };
+                host_log!(Info, "Switching input to {}", def.name.as_str());
+                let was_player_input = on_player_input;
                 mute_out_relay.set_low();
                 Timer::after_millis(100).await;
                 last_sample_rate = None;
-                // select optical or coaxial input
-                if i2s_signal_select.is_set_low() {
-                    info!("Input signal relay set high");
-                    i2s_signal_select.set_high();
-                    flash.save_input(1);
-                    input = 1;
-                    current_input = "USB";
-                    amanero::REFRESH_SAMPLE_RATE.signal(());
-                    let mut d_lock = DISPLAY.lock().await;
-                    let disp = d_lock.as_mut().unwrap();
-                    disp.clear_main_area();
-                    disp.draw_header_status(current_input, current_filter);
-                    disp.draw_playback_mode(current_playback_mode);
-                    if display_mode == DisplayMode::Normal {
-                        disp.redraw_track_info();
-                        disp.draw_progress_bar("00:00", "00:00", 0.0);
-                    }
-                }
-                // select i2s input
-                else {
-                    info!("Input signal relay set low");
-                    i2s_signal_select.set_low();
-                    flash.save_input(0);
-                    input = 0;
-                    current_input = "OPT";
+                input_select.apply(def.select);
+                flash.save_input(target);
+                input = target;
+                on_player_input = def.kind == InputKind::Usb;
+                input_def = def;
+                if !on_player_input {
+                    close_browser(&mut browser, display_mode);
+                }
+                refresh_sample_rate(input_def.kind);
+                if was_player_input && !on_player_input {
+                    rsplayer.send_player(FwPlayerCmd::Stop);
+                }
+                rsplayer.send_input(input, &input_def.name);
+                rsplayer.send_format(SampleRate::Unknown);
+                {
                     let mut d_lock = DISPLAY.lock().await;
                     let disp = d_lock.as_mut().unwrap();
                     disp.clear_main_area();
-                    disp.clear_track_info();
-                    disp.draw_header_status(current_input, current_filter);
-                    disp.draw_playback_mode(PlaybackMode::Sequential);
-                    rsplayer.send_player(FwPlayerCmd::Stop).await;
-                    dac.dsd_pcm(SampleRate::Pcm441).await;
-                    if display_mode == DisplayMode::Normal {
-                        disp.draw_large_volume(current_volume);
-                    } else if display_mode == DisplayMode::BigInfo {
-                        disp.draw_volume(current_volume);
+                    disp.draw_header_status(&input_def.name, current_filter);
+                    disp.draw_footer("", "", "");
+                    if on_player_input {
+                        disp.draw_playback_mode(current_playback_mode);
+                        if display_mode == DisplayMode::Normal {
+                            disp.redraw_track_info();
+                            disp.draw_progress_bar("00:00", "00:00", 0.0);
+                        } else if display_mode == DisplayMode::ArtInfo {
+                            disp.redraw_track_info();
+                            rsplayer.send_art_request();
+                        }
+                    } else {
+                        art = None;
+                        disp.clear_track_info();
+                        disp.clear_position();
+                        disp.draw_playback_mode(PlaybackMode::Sequential);
+                        if display_mode == DisplayMode::Normal {
+                            disp.draw_large_volume(current_volume);
+                        } else if display_mode == DisplayMode::BigInfo {
+                            disp.draw_volume(current_volume);
+                        }
                     }
                 }
                 Timer::after_millis(100).await;
                 mute_out_relay.set_high();
             }
+            Command::SetInputName { index, name } => {
+                if inputs.set_name(index, &name) {
+                    flash.save_input_table(&inputs);
+                    if index == input {
+                        input_def.name = name;
+                        if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                            disp.draw_header_status(&input_def.name, current_filter);
+                        }
+                        rsplayer.send_input(input, &input_def.name);
+                    }
+                }
+            }
+            Command::SetInputEnabled { index, enabled } => {
+                if inputs.set_enabled(index, enabled) {
+                    flash.save_input_table(&inputs);
+                    inputs.publish();
+                    if index == input && !enabled {
+                        // Move off the input that just went away.
+                        CMD_CHANNEL.try_send(Command::NextInput).ok();
+                    }
+                } else {
+                    host_log!(Info, "Input {} cannot be set enabled={}", index, enabled);
+                }
+            }
+            Command::SetInputOrder(order) => {
+                if inputs.set_order(&order) {
+                    flash.save_input_table(&inputs);
+                }
+            }
+            Command::SetInputPolicy(policy) => {
+                host_log!(Info, "Input policy: {:?}", policy);
+                input_policy = policy;
+                flash.save_input_policy(policy as u8);
+            }
 
             Command::Next => {
-                rsplayer.send_player(FwPlayerCmd::Next).await;
+                rsplayer.send_player(FwPlayerCmd::Next);
             }
             Command::Prev => {
-                rsplayer.send_player(FwPlayerCmd::Prev).await;
+                rsplayer.send_player(FwPlayerCmd::Prev);
             }
             Command::SeekForward => {
-                rsplayer.send_player(FwPlayerCmd::SeekForward).await;
+                rsplayer.send_player(FwPlayerCmd::SeekForward);
             }
             Command::SeekBackward => {
-                rsplayer.send_player(FwPlayerCmd::SeekBackward).await;
+                rsplayer.send_player(FwPlayerCmd::SeekBackward);
             }
             Command::TogglePlay => {
-                rsplayer.send_player(FwPlayerCmd::TogglePlay).await;
+                rsplayer.send_player(FwPlayerCmd::TogglePlay);
+            }
+            c @ (Command::BrowseHold
+            | Command::BrowseStep(_)
+            | Command::BrowseSelect
+            | Command::BrowseEnqueue
+            | Command::BrowseBack) => {
+                browse_input = Instant::now();
+                let mut opened = false;
+                match (c, browser.as_mut()) {
+                    (Command::BrowseHold, None) if on_player_input => {
+                        browser = Some(Browser::Queue(QueueBrowser::new()));
+                        rsplayer.send_queue_request(None);
+                        opened = true;
+                    }
+                    (Command::BrowseHold, Some(Browser::Queue(_))) => {
+                        let library = LibraryBrowser::new();
+                        rsplayer.send_library_request(library.query());
+                        browser = Some(Browser::Library(library));
+                        opened = true;
+                    }
+                    (Command::BrowseHold | Command::BrowseBack, Some(Browser::Library(l))) => {
+                        if !l.back() {
+                            close_browser(&mut browser, display_mode);
+                        }
+                    }
+                    (Command::BrowseBack, Some(Browser::Queue(_))) => {
+                        close_browser(&mut browser, display_mode);
+                    }
+                    (Command::BrowseStep(delta), Some(Browser::Queue(q))) => {
+                        if let Some(offset) = q.step(delta.into()) {
+                            rsplayer.send_queue_request(Some(offset));
+                        }
+                    }
+                    (Command::BrowseStep(delta), Some(Browser::Library(l))) => {
+                        if let Some(query) = l.step(delta.into()) {
+                            rsplayer.send_library_request(query);
+                        }
+                    }
+                    (Command::BrowseSelect, Some(Browser::Queue(q))) => {
+                        let Some(index) = q.selected() else {
+                            continue;
+                        };
+                        info!("Playing queue entry {}", index);
+                        rsplayer.send_player(FwPlayerCmd::PlayQueueItem(index));
+                        close_browser(&mut browser, display_mode);
+                    }
+                    (Command::BrowseSelect, Some(Browser::Library(l))) => match l.select() {
+                        Some(Selection::Open(query)) => rsplayer.send_library_request(query),
+                        Some(Selection::Play(id)) => {
+                            info!("Playing library track {}", id);
+                            rsplayer.send_player(FwPlayerCmd::PlayLibraryItem {
+                                level: LibraryLevel::Tracks,
+                                id,
+                            });
+                            close_browser(&mut browser, display_mode);
+                        }
+                        None => continue,
+                    },
+                    (Command::BrowseEnqueue, Some(Browser::Library(l))) => {
+                        let Some((level, id)) = l.selected() else {
+                            continue;
+                        };
+                        info!("Enqueueing library entry {}", id);
+                        rsplayer.send_player(FwPlayerCmd::EnqueueLibraryItem { level, id });
+                    }
+                    _ => continue,
+                }
+                if opened {
+                    browse::BROWSING.store(true, core::sync::atomic::Ordering::Relaxed);
+                }
+                if let Some(b) = &browser {
+                    if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                        if opened {
+                            disp.open_list();
+                        }
+                        draw_browser(disp, b);
+                    }
+                }
             }
-            Command::NextDacFilterType => {
-                info!("got NextDacFilterType");
-                let val = dac.next_filter().await;
+            c @ (Command::NextDacFilterType | Command::SetDacFilterType(_)) => {
+                info!("got DAC filter change");
+                let change = match c {
+                    Command::SetDacFilterType(val) => Change::Set(val),
+                    _ => Change::Next,
+                };
+                let Some(val) = dac::FILTER.resolve(dac.filter_type, change) else {
+                    host_log!(Warn, "Filter not supported: {}", change);
+                    continue;
+                };
+                dac.set_filter(val).await;
                 flash.save_filter_type(val);
                 filter_val = val;
                 current_filter = FilterType::from(val).as_str();
                 if let Some(disp) = DISPLAY.lock().await.as_mut() {
-                    disp.draw_header_status(current_input, current_filter);
+                    disp.draw_header_status(&input_def.name, current_filter);
                 }
+                rsplayer.send_filter(val);
             }
-            Command::NextDacSoundSetting => {
-                info!("got NextDacSoundSetting");
-                let val = dac.next_sound_setting().await;
+            c @ (Command::NextDacSoundSetting | Command::SetDacSoundSetting(_)) => {
+                info!("got DAC sound setting change");
+                let change = match c {
+                    Command::SetDacSoundSetting(val) => Change::Set(val),
+                    _ => Change::Next,
+                };
+                let Some(val) = dac::SOUND_SETTING.resolve(dac.sound_setting, change) else {
+                    host_log!(Warn, "Sound setting not supported: {}", change);
+                    continue;
+                };
+                dac.set_sound_setting(val).await;
                 flash.save_sound_setting(val);
+                rsplayer.send_sound_setting(val);
             }
 
             Command::QueryCurrentVolume => {
                 // RAM value, not flash — flash may lag behind while a
                 // deferred save is pending.
-                rsplayer.send_current_volume(current_volume).await;
+                rsplayer.send_current_volume(current_volume);
+            }
+            c @ (Command::UsbConnected | Command::HostHello { .. } | Command::QueryState) => {
+                if c == Command::UsbConnected {
+                    ir::release_host();
+                }
+                if c != Command::QueryState {
+                    rsplayer.send_hello();
+                    rsplayer.send_reset_reason(reset_reason);
+                }
+                if c == (Command::HostHello { compatible: false }) {
+                    continue;
+                }
+                debug!("Reporting device state");
+                let rate = last_sample_rate.unwrap_or(SampleRate::Unknown);
+                rsplayer.send_state(DeviceState {
+                    power: is_power_on,
+                    volume: current_volume,
+                    input,
+                    input_name: input_def.name.as_str().try_into().unwrap_or_default(),
+                    filter: filter_val,
+                    sound_setting: dac.sound_setting,
+                    display_mode: display_mode as u8,
+                    playback_mode: current_playback_mode,
+                    dsd: rate.is_dsd(),
+                    rate_hz: rate.hz(),
+                    muted: mute_out_relay.is_set_low(),
+                    dac_fault,
+                    hid_mode: rsplayer.hid_mode() as u8,
+                });
+            }
+            Command::ForwardIr {
+                address,
+                command,
+                repeat,
+            } => {
+                rsplayer.send_ir(address, command, repeat);
+            }
+            Command::SetIrLearn(on) => {
+                host_log!(Info, "IR learn mode: {}", on);
+                ir::set_learn(on);
+            }
+            Command::SetIrForward {
+                address,
+                command,
+                forward,
+            } => {
+                if !ir::set_forwarded(address, command, forward) {
+                    host_log!(
+                        Info,
+                        "IR forward list full, {}/{} not added",
+                        address,
+                        command
+                    );
+                }
             }
-            Command::UsbConnected => {
-                info!("USB host connected, reporting state");
-                rsplayer.send_power_state(is_power_on).await;
-                if is_power_on {
-                    rsplayer.send_current_volume(current_volume).await;
+            Command::ClearIrForward => ir::clear_forwarded(),
+            Command::DfuBegin { size, crc32 } => {
+                let status = match dfu.begin(size, crc32) {
+                    Ok(()) => DfuStatus::Ready,
+                    Err(e) => DfuStatus::Failed(e),
+                };
+                rsplayer.send_dfu_status(status);
+            }
+            Command::DfuFinish => match dfu.finish() {
+                Ok(()) => {
+                    rsplayer.send_dfu_status(DfuStatus::Verified);
+                    let pending_volume = volume_dirty_since.map(|_| current_volume);
+                    prepare_reset(&mut flash, pending_volume, &mut mute_out_relay, &mut dac).await;
+                    supervisor::record_reset(ResetReason::Update);
+                    cortex_m::peripheral::SCB::sys_reset();
                 }
+                Err(e) => rsplayer.send_dfu_status(DfuStatus::Failed(e)),
+            },
+            Command::DfuAbort => dfu.abort(),
+            Command::SetLogLevel(level) => hostlog::set_level(level),
+            Command::HostHeartbeat => {
+                last_host_heartbeat = Some(Instant::now());
+                if host_offline {
+                    host_offline = false;
+                    host_log!(Info, "Host heartbeat back");
+                    if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                        disp.draw_host_status(true);
+                    }
+                }
+            }
+            Command::SetHostRecovery(secs) => {
+                host_log!(Info, "Host recovery grace: {}s", secs);
+                host_recovery_secs = secs;
+                flash.save_host_recovery(secs);
+            }
+            Command::SetHidMode(mode) => {
+                host_log!(Info, "HID mode: {:?}", mode);
+                rsplayer.set_hid_mode(mode);
+                flash.save_hid_mode(mode as u8);
             }
-            Command::UpdateSampleRate(rate) => {
-                if input != 1 {
+            Command::Mute => {
+                if rsplayer.hid_mode().host_volume() {
+                    rsplayer.send_volume_key(MediaKey::Mute);
+                }
+            }
+            Command::RequestReset(kind) => {
+                // Low timer bits: not a secret, just never a value a stray or
+                // replayed frame would carry.
+                let token = (Instant::now().as_ticks() as u32) | 1;
+                pending_reset = Some((kind, token, Instant::now()));
+                rsplayer.send_reset_token(kind, token);
+            }
+            Command::ConfirmReset { kind, token } => {
+                let confirmed = pending_reset.take().is_some_and(|(k, t, at)| {
+                    k == kind && t == token && at.elapsed().as_secs() < RESET_CONFIRM_SECS
+                });
+                if !confirmed {
+                    host_log!(Warn, "Reset not confirmed, ignoring");
+                    continue;
+                }
+                host_log!(Info, "Host requested reset: {:?}", kind);
+                let pending_volume = volume_dirty_since.map(|_| current_volume);
+                prepare_reset(&mut flash, pending_volume, &mut mute_out_relay, &mut dac).await;
+                supervisor::record_reset(ResetReason::Host);
+                match kind {
+                    ResetKind::Bootloader => embassy_rp::rom_data::reset_to_usb_boot(0, 0),
+                    ResetKind::Soft => cortex_m::peripheral::SCB::sys_reset(),
+                }
+            }
+            Command::UpdateSampleRate(source, rate) => {
+                // Activity bookkeeping for the auto policies happens before
+                // the selected-input filter below.
+                let activated_kind = match source {
+                    RateSource::Amanero => {
+                        let was = core::mem::replace(&mut usb_signal, rate != SampleRate::Unknown);
+                        (!was && usb_signal).then_some(InputKind::Usb)
+                    }
+                    #[cfg(feature = "wm8805")]
+                    RateSource::Spdif => {
+                        let was = core::mem::replace(&mut spdif_signal, rate != SampleRate::Unknown);
+                        (!was && spdif_signal).then_some(InputKind::Spdif)
+                    }
+                    RateSource::Meter => None,
+                };
+                if let Some(kind) = activated_kind.filter(|&k| k != input_def.kind) {
+                    let target = inputs.first_of_kind(kind);
+                    // The selected input counts as playing once a rate
+                    // has been applied for it.
+                    let current_playing = last_sample_rate.is_some();
+                    let switch = match input_policy {
+                        InputPolicy::Manual => false,
+                        // Newest activity wins, except that a newly locked
+                        // S/PDIF stream does not interrupt playing USB —
+                        // that direction only happens via the silence
+                        // fallback.
+                        InputPolicy::Auto => kind == InputKind::Usb || !current_playing,
+                        InputPolicy::AutoPriority => target
+                            .is_some_and(|t| !current_playing || inputs.rank(t) < inputs.rank(input)),
+                    };
+                    if let Some(t) = target.filter(|_| switch) {
+                        host_log!(Info, "Signal on {:?}, auto-switching to input {}", kind, t);
+                        CMD_CHANNEL.try_send(Command::SelectInput(t)).ok();
+                    }
+                }
+
+                if source != RateSource::for_input(input_def.kind) {
                     continue;
                 }
                 debug!("Sample rate command: {}", rate);
@@ -677,6 +1521,7 @@ pub async fn process_commands(
                 if last_sample_rate == Some(rate) {
                     continue;
                 }
+                host_log!(Info, "Sample rate: {:?}", rate);
                 mute_out_relay.set_low();
                 Timer::after_millis(50).await;
                 dac.dsd_pcm(rate).await;
@@ -687,6 +1532,27 @@ pub async fn process_commands(
                 if let Some(disp) = DISPLAY.lock().await.as_mut() {
                     disp.draw_footer(format, freq, bit_depth);
                 }
+                rsplayer.send_format(rate);
+            }
+            #[cfg(feature = "wm8805")]
+            Command::UpdateSpdifStatus(status) => {
+                if input_def.kind != InputKind::Spdif {
+                    continue;
+                }
+                host_log!(Info, "S/PDIF status: {:?}", status);
+                if status.playable_rate() == SampleRate::Unknown {
+                    // Unlocked or non-PCM data: mute right away. The rate
+                    // path unmutes once a playable rate is reported again,
+                    // so forget the last one or a relock at the same rate
+                    // would be skipped as a no-op.
+                    mute_out_relay.set_low();
+                    last_sample_rate = None;
+                    let format = if status.locked { "DATA" } else { "UNLOCK" };
+                    if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                        disp.draw_footer(format, "", "");
+                    }
+                    rsplayer.send_format(SampleRate::Unknown);
+                }
             }
             Command::UpdateTrackInfo {
                 title,
@@ -694,11 +1560,139 @@ pub async fn process_commands(
                 album,
             } => {
                 silence_start_time = None;
-                if display_mode != DisplayMode::VuMeter && current_input != "OPT" {
-                    if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                // A new track makes the cover in flight, and the one shown,
+                // stale; the host follows up with the new cover.
+                let changed = current_track
+                    .as_ref()
+                    .is_none_or(|(t, a, l)| (t, a, l) != (&title, &artist, &album));
+                if changed && art.take().is_some() {
+                    debug!("Track changed, cover transfer cancelled");
+                }
+                let mut disp_lock = DISPLAY.lock().await;
+                if let Some(disp) = disp_lock.as_mut() {
+                    if changed {
+                        disp.clear_art();
+                        // The running clock belongs to the old track; the
+                        // host sends the new position next.
+                        disp.clear_position();
+                        if display_mode == DisplayMode::Normal && on_player_input {
+                            disp.draw_progress_bar("00:00", "00:00", 0.0);
+                        }
+                    }
+                    if display_mode != DisplayMode::VuMeter && on_player_input {
                         disp.draw_track_info(&title, &artist, &album);
                     }
                 }
+                current_track = Some((title, artist, album));
+            }
+            Command::UpdateTrackExt(ext) => {
+                if display_mode != DisplayMode::VuMeter && on_player_input {
+                    if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                        disp.draw_track_ext(&ext);
+                    }
+                }
+            }
+            Command::UpdateUpNext { title, artist } => {
+                if display_mode != DisplayMode::VuMeter && on_player_input {
+                    if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                        disp.draw_up_next(&title, &artist);
+                    }
+                }
+            }
+            Command::ArtBegin {
+                id,
+                width,
+                height,
+                format,
+            } => {
+                art = art::ArtTransfer::begin(id, width, height, format);
+                if art.is_none() {
+                    host_log!(Info, "Cover {}x{} does not fit, ignored", width, height);
+                }
+                if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                    disp.clear_art();
+                }
+            }
+            Command::ArtCancel => {
+                art = None;
+            }
+            Command::Bulk => {
+                let Ok(bulk) = BULK_SLOT.try_receive() else {
+                    continue;
+                };
+                if !bulk.allowed_while_off() && !is_power_on {
+                    info!("Power is off, ignoring command");
+                    continue;
+                }
+                match bulk {
+                    Bulk::DfuChunk { offset, data } => match dfu.chunk(offset, &data) {
+                        Ok(received) => {
+                            if received % 4096 == 0 {
+                                rsplayer.send_dfu_status(DfuStatus::Progress(received));
+                            }
+                        }
+                        Err(e) => rsplayer.send_dfu_status(DfuStatus::Failed(e)),
+                    },
+                    Bulk::ArtChunk { id, offset, data } => {
+                        let Some(transfer) = art.as_mut() else {
+                            continue;
+                        };
+                        let Some(first_px) = transfer.chunk(id, offset, data.len()) else {
+                            continue;
+                        };
+                        if on_player_input {
+                            if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                                disp.draw_art(
+                                    transfer.width,
+                                    transfer.height,
+                                    transfer.format,
+                                    first_px,
+                                    &data,
+                                );
+                            }
+                        }
+                        if transfer.done() {
+                            debug!("Cover {} complete", id);
+                            art = None;
+                        }
+                    }
+                    Bulk::UpdateQueuePage {
+                        offset,
+                        total,
+                        current,
+                        items,
+                    } => {
+                        let Some(Browser::Queue(q)) = browser.as_mut() else {
+                            continue;
+                        };
+                        q.page(offset, total, current, items);
+                        if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                            draw_browser(disp, browser.as_ref().unwrap());
+                        }
+                    }
+                    Bulk::UpdateLibraryPage {
+                        query,
+                        total,
+                        items,
+                    } => {
+                        let Some(Browser::Library(l)) = browser.as_mut() else {
+                            continue;
+                        };
+                        l.page(query, total, items);
+                        if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                            draw_browser(disp, browser.as_ref().unwrap());
+                        }
+                    }
+                }
+            }
+            Command::Notify {
+                text,
+                severity,
+                duration_ms,
+            } => {
+                if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                    disp.notify(&text, severity, duration_ms);
+                }
             }
             Command::UpdateProgress {
                 current,
@@ -706,12 +1700,28 @@ pub async fn process_commands(
                 percent,
             } => {
                 silence_start_time = None;
-                if display_mode == DisplayMode::Normal && current_input != "OPT" {
-                    if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                    disp.clear_position();
+                    if display_mode == DisplayMode::Normal && on_player_input {
                         disp.draw_progress_bar(&current, &total, percent as f32 / 100.0);
                     }
                 }
             }
+            Command::UpdatePosition {
+                position_ms,
+                duration_ms,
+                playing,
+            } => {
+                // A paused position still counts as silence.
+                if playing {
+                    silence_start_time = None;
+                }
+                if on_player_input {
+                    if let Some(disp) = DISPLAY.lock().await.as_mut() {
+                        disp.set_position(position_ms, duration_ms, playing);
+                    }
+                }
+            }
             Command::UpdatePlaybackMode(mode) => {
                 debug!("UpdatePlaybackMode received: {:?}", mode);
                 current_playback_mode = mode;
@@ -730,6 +1740,14 @@ pub async fn process_commands(
                     }
                 }
             }
+            Command::UpdateSpectrum(bands) => {
+                silence_start_time = None;
+                if display_mode == DisplayMode::Spectrum {
+                    if let Some(d) = DISPLAY.lock().await.as_mut() {
+                        d.draw_spectrum(&bands);
+                    }
+                }
+            }
             _ => {}
         }
     }
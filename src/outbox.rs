@@ -0,0 +1,151 @@
+use heapless::Vec;
+
+/// What a full outbox may do with a queued message.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Class {
+    /// Log line: the first to make room.
+    Log,
+    /// Reports the host can do without; the oldest makes room once no log
+    /// line is left.
+    Droppable,
+    /// Replies and power changes: the host waits for the former and acts
+    /// on the latter, so they are never dropped.
+    Kept,
+}
+
+/// A message the outbox queue can classify and merge.
+pub trait Queued {
+    fn class(&self) -> Class;
+
+    /// Takes over `new` if `self` is a state report of the same kind
+    /// (volume, power) the host only needs the latest of.
+    fn merge(&mut self, new: &Self) -> bool;
+}
+
+/// Outcome of [`push`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pushed {
+    Queued,
+    /// Folded into a queued message of the same kind.
+    Merged,
+    /// Queued after dropping an older message.
+    Evicted,
+    /// Not queued: nothing may make room for it.
+    Refused,
+}
+
+/// Queues `item`, making room when full: the oldest log line goes first,
+/// then the oldest droppable message; a log line never pushes out anything
+/// else, and a kept message is never removed.
+pub fn push<T: Queued, const N: usize>(q: &mut Vec<T, N>, item: T) -> Pushed {
+    if q.iter_mut().any(|i| i.merge(&item)) {
+        return Pushed::Merged;
+    }
+    if !q.is_full() {
+        return match q.push(item) {
+            Ok(()) => Pushed::Queued,
+            Err(_) => Pushed::Refused,
+        };
+    }
+    let victim = match q.iter().position(|i| i.class() == Class::Log) {
+        Some(oldest) => Some(oldest),
+        None if item.class() == Class::Log => None,
+        None => q.iter().position(|i| i.class() == Class::Droppable),
+    };
+    let Some(victim) = victim else {
+        return Pushed::Refused;
+    };
+    q.remove(victim);
+    match q.push(item) {
+        Ok(()) => Pushed::Evicted,
+        Err(_) => Pushed::Refused,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    enum Msg {
+        Ack(u16),
+        Power(bool),
+        Volume(u8),
+        Log(u8),
+        Report(u8),
+    }
+
+    impl Queued for Msg {
+        fn class(&self) -> Class {
+            match self {
+                Msg::Log(_) => Class::Log,
+                Msg::Ack(_) | Msg::Power(_) => Class::Kept,
+                Msg::Volume(_) | Msg::Report(_) => Class::Droppable,
+            }
+        }
+
+        fn merge(&mut self, new: &Self) -> bool {
+            match (self, new) {
+                (Msg::Volume(v), Msg::Volume(n)) => *v = *n,
+                (Msg::Power(p), Msg::Power(n)) => *p = *n,
+                _ => return false,
+            }
+            true
+        }
+    }
+
+    const N: usize = 4;
+
+    fn queue(items: &[Msg]) -> Vec<Msg, N> {
+        let mut q = Vec::new();
+        for &item in items {
+            push(&mut q, item);
+        }
+        q
+    }
+
+    #[test]
+    fn replies_and_power_changes_are_never_lost() {
+        let mut q = queue(&[Msg::Ack(1), Msg::Power(true), Msg::Ack(2), Msg::Ack(3)]);
+        // Power changes fold into the queued one instead of filling up.
+        assert_eq!(push(&mut q, Msg::Power(false)), Pushed::Merged);
+        assert_eq!(push(&mut q, Msg::Power(true)), Pushed::Merged);
+        // Nothing left that may go: the newcomer is refused, not a reply.
+        assert_eq!(push(&mut q, Msg::Ack(4)), Pushed::Refused);
+        assert_eq!(push(&mut q, Msg::Report(1)), Pushed::Refused);
+        assert_eq!(push(&mut q, Msg::Log(1)), Pushed::Refused);
+        assert_eq!(
+            q.as_slice(),
+            [Msg::Ack(1), Msg::Power(true), Msg::Ack(2), Msg::Ack(3)]
+        );
+    }
+
+    #[test]
+    fn logs_make_room_first() {
+        let mut q = queue(&[Msg::Report(1), Msg::Log(1), Msg::Ack(1), Msg::Log(2)]);
+        assert_eq!(push(&mut q, Msg::Ack(2)), Pushed::Evicted);
+        assert_eq!(
+            q.as_slice(),
+            [Msg::Report(1), Msg::Ack(1), Msg::Log(2), Msg::Ack(2)]
+        );
+        // A log line only displaces another log line.
+        assert_eq!(push(&mut q, Msg::Log(3)), Pushed::Evicted);
+        assert_eq!(
+            q.as_slice(),
+            [Msg::Report(1), Msg::Ack(1), Msg::Ack(2), Msg::Log(3)]
+        );
+        assert_eq!(push(&mut q, Msg::Power(true)), Pushed::Evicted);
+        assert_eq!(push(&mut q, Msg::Log(4)), Pushed::Refused);
+    }
+
+    #[test]
+    fn oldest_droppable_goes_when_no_log_is_queued() {
+        let mut q = queue(&[Msg::Ack(1), Msg::Report(1), Msg::Volume(5), Msg::Report(2)]);
+        assert_eq!(push(&mut q, Msg::Volume(6)), Pushed::Merged);
+        assert_eq!(push(&mut q, Msg::Ack(2)), Pushed::Evicted);
+        assert_eq!(
+            q.as_slice(),
+            [Msg::Ack(1), Msg::Volume(6), Msg::Report(2), Msg::Ack(2)]
+        );
+    }
+}
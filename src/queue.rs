@@ -0,0 +1,64 @@
+use heapless::{String, Vec};
+use rsplayer_wire::{QUEUE_LABEL_LEN, QUEUE_PAGE};
+
+use crate::browse::PagedList;
+
+pub type QueueLabel = String<QUEUE_LABEL_LEN>;
+
+/// Window onto the host's play queue.
+pub struct QueueBrowser {
+    list: PagedList<QueueLabel>,
+    /// Index of the track playing now, if it is in the queue.
+    current: Option<u32>,
+}
+
+impl QueueBrowser {
+    pub fn new() -> Self {
+        QueueBrowser {
+            list: PagedList::new(),
+            current: None,
+        }
+    }
+
+    /// Takes a page from the host. The first one, sent for the current
+    /// track, puts the cursor on that track.
+    pub fn page(
+        &mut self,
+        offset: u32,
+        total: u32,
+        current: Option<u32>,
+        items: Vec<QueueLabel, QUEUE_PAGE>,
+    ) {
+        self.current = current;
+        self.list.page(offset, total, items, current);
+    }
+
+    /// See [`PagedList::step`].
+    pub fn step(&mut self, delta: i32) -> Option<u32> {
+        self.list.step(delta)
+    }
+
+    /// Queue index under the cursor, once its page has arrived.
+    pub fn selected(&self) -> Option<u32> {
+        self.list.selected_index()
+    }
+
+    pub fn title(&self) -> String<24> {
+        self.list.title("Queue")
+    }
+
+    pub fn items(&self) -> &[QueueLabel] {
+        self.list.items()
+    }
+
+    /// Rows of the held page under the cursor and playing now.
+    pub fn rows(&self) -> (Option<usize>, Option<usize>) {
+        (self.list.selected_row(), self.list.row(self.current))
+    }
+}
+
+impl Default for QueueBrowser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
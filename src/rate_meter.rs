@@ -0,0 +1,169 @@
+use defmt::debug;
+use embassy_futures::select::{select, Either};
+use embassy_rp::peripherals::PIO0;
+use embassy_rp::pio::{Common, Config, Direction, Instance, LoadedProgram, PioPin, StateMachine};
+use embassy_rp::Peri;
+use embassy_sync::signal::Signal;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Sender};
+use embassy_time::{Instant, Timer};
+use fixed::traits::ToFixed;
+
+use crate::dac::common::SampleRate;
+use crate::supervisor;
+use crate::{Command, RateSource};
+
+mod classify;
+use classify::classify;
+
+/// Forces the meter to report its current reading even if it did not change,
+/// e.g. after the input relay was switched back to the optical path.
+pub static REFRESH_MEASURED_RATE: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Gate time of one measurement. 200 ms keeps the ±1 edge error and the
+/// async timer jitter well under the 8% gap between 44.1k and 48k families.
+const GATE_MS: u64 = 200;
+
+/// Counts rising edges on one pin: X is decremented once per edge and read
+/// out by the CPU with forced `mov isr, x` / `push` instructions, so the
+/// program never has to poll for a read request between edges. Three
+/// instructions per edge at full system clock limit it to ~40 MHz inputs
+/// ([`classify::MAX_CLOCK_HZ`]); rates needing a faster BCLK read as
+/// `Unknown`.
+pub struct PioEdgeCounterProgram<'a, PIO: Instance> {
+    prg: LoadedProgram<'a, PIO>,
+}
+
+impl<'a, PIO: Instance> PioEdgeCounterProgram<'a, PIO> {
+    pub fn new(common: &mut Common<'a, PIO>) -> Self {
+        let prg = pio::pio_asm!(
+            ".wrap_target",
+            "top:",
+            "    wait 0 pin 0",
+            "    wait 1 pin 0",
+            "    jmp x-- top",
+            ".wrap",
+        );
+        let prg = common.load_program(&prg.program);
+        Self { prg }
+    }
+}
+
+pub struct PioEdgeCounter<'d, T: Instance, const SM: usize> {
+    sm: StateMachine<'d, T, SM>,
+    last: u32,
+}
+
+impl<'d, T: Instance, const SM: usize> PioEdgeCounter<'d, T, SM> {
+    pub fn new(
+        common: &mut Common<'d, T>,
+        mut sm: StateMachine<'d, T, SM>,
+        pin: Peri<'d, impl PioPin>,
+        program: &PioEdgeCounterProgram<'d, T>,
+    ) -> Self {
+        let pin = common.make_pio_pin(pin);
+        sm.set_pin_dirs(Direction::In, &[&pin]);
+        let mut cfg = Config::default();
+        cfg.set_in_pins(&[&pin]);
+        cfg.clock_divider = 1.to_fixed();
+        cfg.use_program(&program.prg, &[]);
+        sm.set_config(&cfg);
+        sm.set_enable(true);
+        let mut counter = Self { sm, last: 0 };
+        counter.last = counter.read_raw();
+        counter
+    }
+
+    fn read_raw(&mut self) -> u32 {
+        let mov = pio::InstructionOperands::MOV {
+            destination: pio::MovDestination::ISR,
+            op: pio::MovOperation::None,
+            source: pio::MovSource::X,
+        };
+        let push = pio::InstructionOperands::PUSH {
+            if_full: false,
+            block: false,
+        };
+        // SAFETY: neither instruction touches the program counter, the
+        // interrupted `wait` simply resumes afterwards.
+        unsafe {
+            self.sm.exec_instr(mov.encode());
+            self.sm.exec_instr(push.encode());
+        }
+        self.sm.rx().try_pull().unwrap_or(self.last)
+    }
+
+    /// Edges seen since the previous call. X counts down, so the delta is
+    /// `last - now` (wrapping).
+    pub fn take_count(&mut self) -> u32 {
+        let now = self.read_raw();
+        let count = self.last.wrapping_sub(now);
+        self.last = now;
+        count
+    }
+}
+
+pub struct RateMeter {
+    lrck: PioEdgeCounter<'static, PIO0, 1>,
+    bclk: PioEdgeCounter<'static, PIO0, 2>,
+}
+
+impl RateMeter {
+    pub fn new(
+        lrck: PioEdgeCounter<'static, PIO0, 1>,
+        bclk: PioEdgeCounter<'static, PIO0, 2>,
+    ) -> Self {
+        RateMeter { lrck, bclk }
+    }
+
+    /// Measures LRCK and BCLK over one gate period, returning both in Hz.
+    pub async fn measure(&mut self) -> (u32, u32) {
+        self.lrck.take_count();
+        self.bclk.take_count();
+        let start = Instant::now();
+        Timer::after_millis(GATE_MS).await;
+        let lrck = self.lrck.take_count();
+        let bclk = self.bclk.take_count();
+        let elapsed_us = start.elapsed().as_micros().max(1);
+        (
+            (lrck as u64 * 1_000_000 / elapsed_us) as u32,
+            (bclk as u64 * 1_000_000 / elapsed_us) as u32,
+        )
+    }
+}
+
+#[embassy_executor::task]
+pub async fn listen_measured_rate(
+    control: Sender<'static, CriticalSectionRawMutex, Command, 64>,
+    mut meter: RateMeter,
+) {
+    let mut reported: Option<SampleRate> = None;
+    let mut candidate = SampleRate::Unknown;
+    let mut refresh = false;
+    loop {
+        // Runs every ~250ms on core1, so it doubles as that core's check-in.
+        supervisor::check_in(supervisor::Task::Core1);
+        let (lrck, bclk) = meter.measure().await;
+        let rate = classify(lrck, bclk);
+        // Require two equal readings in a row so a single gate straddling a
+        // rate change is not reported.
+        let stable = rate == candidate;
+        candidate = rate;
+        // Never waits for queue room: a full command queue must not stall
+        // the check-in above. A reading that did not fit goes out on a
+        // later gate.
+        if ((stable && reported != Some(rate)) || refresh)
+            && control
+                .try_send(Command::UpdateSampleRate(RateSource::Meter, rate))
+                .is_ok()
+        {
+            debug!("measured lrck: {} Hz, bclk: {} Hz -> {}", lrck, bclk, rate);
+            reported = Some(rate);
+            refresh = false;
+        }
+        // Idle between gates; a refresh request cuts the pause short.
+        refresh |= matches!(
+            select(REFRESH_MEASURED_RATE.wait(), Timer::after_millis(50)).await,
+            Either::First(_)
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use crate::dac::sample_rate::SampleRate;
+
+/// Highest clock the PIO edge counter follows: three instructions per edge
+/// at the 125 MHz system clock is ~41 MHz. Faster clocks lose edges and
+/// read as some lower, meaningless frequency, so rates that only come with
+/// such a clock are left out of the tables below: 1411.2/1536 kHz PCM (BCLK
+/// 45 MHz and up even at 32fs) and DSD1024 (45 MHz). 768 kHz PCM is only
+/// measurable at 32 or 48fs.
+pub const MAX_CLOCK_HZ: u32 = 40_000_000;
+
+const PCM_RATES: [(u32, SampleRate); 11] = [
+    (32_000, SampleRate::Pcm32),
+    (44_100, SampleRate::Pcm441),
+    (48_000, SampleRate::Pcm48),
+    (88_200, SampleRate::Pcm882),
+    (96_000, SampleRate::Pcm96),
+    (176_400, SampleRate::Pcm1764),
+    (192_000, SampleRate::Pcm192),
+    (352_800, SampleRate::Pcm3528),
+    (384_000, SampleRate::Pcm384),
+    (705_600, SampleRate::Pcm7056),
+    (768_000, SampleRate::Pcm768),
+];
+
+const DSD_CLOCKS: [(u32, SampleRate); 4] = [
+    (2_822_400, SampleRate::Dsd64),
+    (5_644_800, SampleRate::Dsd128),
+    (11_289_600, SampleRate::Dsd256),
+    (22_579_200, SampleRate::Dsd512),
+];
+
+/// Below this BCLK the line is considered idle: the slowest valid stream
+/// (32 kHz PCM at 32fs) still clocks at 1.024 MHz.
+const MIN_BCLK_HZ: u32 = 900_000;
+
+fn nearest(freq: u32, table: &[(u32, SampleRate)]) -> SampleRate {
+    // ±2% window around each nominal rate.
+    table
+        .iter()
+        .find(|(nominal, _)| freq.abs_diff(*nominal) <= nominal / 50)
+        .map(|(_, rate)| *rate)
+        .unwrap_or(SampleRate::Unknown)
+}
+
+/// Classifies a measured LRCK/BCLK pair. PCM runs BCLK at 32, 48 or 64
+/// times LRCK; in DSD mode the LRCK line carries channel data instead, whose
+/// rising edges come at a small fraction of the bit clock, and BCLK itself is
+/// the DSD clock. Pure function so it can be exercised with synthetic
+/// readings off-target.
+pub fn classify(lrck_hz: u32, bclk_hz: u32) -> SampleRate {
+    if !(MIN_BCLK_HZ..=MAX_CLOCK_HZ).contains(&bclk_hz) {
+        return SampleRate::Unknown;
+    }
+    if let Some(ratio) = bclk_hz.checked_div(lrck_hz) {
+        if (28..=70).contains(&ratio) {
+            return nearest(lrck_hz, &PCM_RATES);
+        }
+        if ratio >= 16 {
+            // Neither a PCM frame nor DSD data — unlocked or glitching.
+            return SampleRate::Unknown;
+        }
+    }
+    nearest(bclk_hz, &DSD_CLOCKS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [SampleRate; 19] = [
+        SampleRate::Pcm32,
+        SampleRate::Pcm441,
+        SampleRate::Pcm48,
+        SampleRate::Pcm882,
+        SampleRate::Pcm96,
+        SampleRate::Pcm1764,
+        SampleRate::Pcm192,
+        SampleRate::Pcm3528,
+        SampleRate::Pcm384,
+        SampleRate::Pcm7056,
+        SampleRate::Pcm768,
+        SampleRate::Pcm14112,
+        SampleRate::Pcm1536,
+        SampleRate::Dsd64,
+        SampleRate::Dsd128,
+        SampleRate::Dsd256,
+        SampleRate::Dsd512,
+        SampleRate::Dsd1024,
+        SampleRate::Unknown,
+    ];
+
+    /// Rates whose clock is above `MAX_CLOCK_HZ` at any bit clock ratio.
+    const UNMEASURABLE: [SampleRate; 3] = [
+        SampleRate::Pcm14112,
+        SampleRate::Pcm1536,
+        SampleRate::Dsd1024,
+    ];
+
+    /// BCLK for `hz` at 64fs, or 32fs where 64fs is beyond the counter.
+    fn pcm_bclk(hz: u32) -> u32 {
+        if hz * 64 <= MAX_CLOCK_HZ {
+            hz * 64
+        } else {
+            hz * 32
+        }
+    }
+
+    #[test]
+    fn every_rate_is_classified() {
+        for rate in ALL {
+            let hz = rate.hz();
+            let got = if rate == SampleRate::Unknown {
+                classify(0, 0)
+            } else if rate.is_dsd() {
+                // Channel data on LRCK, edges at a quarter of the DSD clock.
+                classify(hz / 4, hz)
+            } else {
+                classify(hz, hz * 32)
+            };
+            let want = if UNMEASURABLE.contains(&rate) {
+                SampleRate::Unknown
+            } else {
+                rate
+            };
+            assert_eq!(got, want, "{:?}", rate);
+        }
+    }
+
+    #[test]
+    fn above_measurable_limit() {
+        // 768 kHz at 64fs, the rates left out of the tables, and any clock
+        // the counter cannot follow.
+        assert_eq!(classify(768_000, 768_000 * 64), SampleRate::Unknown);
+        assert_eq!(classify(1_536_000, 1_536_000 * 32), SampleRate::Unknown);
+        assert_eq!(classify(1_411_200, 1_411_200 * 32), SampleRate::Unknown);
+        assert_eq!(classify(0, 45_158_400), SampleRate::Unknown);
+        assert_eq!(classify(11_289_600, 45_158_400), SampleRate::Unknown);
+        for lrck in [0, 48_000, 768_000, MAX_CLOCK_HZ / 16] {
+            assert_eq!(classify(lrck, MAX_CLOCK_HZ + 1), SampleRate::Unknown);
+            assert_eq!(classify(lrck, u32::MAX), SampleRate::Unknown);
+        }
+        // 768 kHz stays measurable at the lower ratios.
+        assert_eq!(classify(768_000, 768_000 * 48), SampleRate::Pcm768);
+    }
+
+    #[test]
+    fn tables_match_nominal_rates() {
+        for (hz, rate) in PCM_RATES.iter().chain(DSD_CLOCKS.iter()) {
+            assert_eq!(rate.hz(), *hz, "{:?}", rate);
+        }
+    }
+
+    #[test]
+    fn pcm_bit_clock_ratios() {
+        for fs in [32, 48, 64] {
+            for (hz, rate) in PCM_RATES {
+                if (MIN_BCLK_HZ..=MAX_CLOCK_HZ).contains(&(hz * fs)) {
+                    assert_eq!(classify(hz, hz * fs), rate, "{:?} at {}fs", rate, fs);
+                }
+            }
+        }
+        // Edges of the accepted BCLK/LRCK ratio.
+        assert_eq!(classify(48_000, 48_000 * 28), SampleRate::Pcm48);
+        assert_eq!(classify(48_000, 48_000 * 70 + 47_999), SampleRate::Pcm48);
+        assert_eq!(classify(48_000, 48_000 * 27), SampleRate::Unknown);
+        assert_eq!(classify(48_000, 48_000 * 71), SampleRate::Unknown);
+    }
+
+    #[test]
+    fn dsd_ratio_detection() {
+        for (hz, rate) in DSD_CLOCKS {
+            // Silent data line, and data edges at various rates below 1/16.
+            for lrck in [0, hz / 2, hz / 8, hz / 15] {
+                assert_eq!(classify(lrck, hz), rate, "{:?} lrck {}", rate, lrck);
+            }
+            // Too many clocks per data edge for DSD, too few for PCM.
+            assert_eq!(classify(hz / 20, hz), SampleRate::Unknown);
+        }
+    }
+
+    #[test]
+    fn tolerance_edges() {
+        for (hz, rate) in PCM_RATES {
+            let tol = hz / 50;
+            assert_eq!(classify(hz - tol, pcm_bclk(hz - tol)), rate);
+            assert_eq!(classify(hz + tol, pcm_bclk(hz + tol)), rate);
+            assert_eq!(
+                classify(hz + tol + 1, pcm_bclk(hz + tol + 1)),
+                SampleRate::Unknown
+            );
+            if pcm_bclk(hz - tol - 1) >= MIN_BCLK_HZ {
+                assert_eq!(
+                    classify(hz - tol - 1, pcm_bclk(hz - tol - 1)),
+                    SampleRate::Unknown
+                );
+            }
+        }
+        for (hz, rate) in DSD_CLOCKS {
+            let tol = hz / 50;
+            assert_eq!(classify(0, hz - tol), rate);
+            assert_eq!(classify(0, hz + tol), rate);
+            assert_eq!(classify(0, hz - tol - 1), SampleRate::Unknown);
+            assert_eq!(classify(0, hz + tol + 1), SampleRate::Unknown);
+        }
+    }
+
+    #[test]
+    fn no_clock() {
+        assert_eq!(classify(0, 0), SampleRate::Unknown);
+        assert_eq!(classify(44_100, 0), SampleRate::Unknown);
+        assert_eq!(classify(0, MIN_BCLK_HZ - 1), SampleRate::Unknown);
+        assert_eq!(classify(14_000, MIN_BCLK_HZ - 1), SampleRate::Unknown);
+        // Idle line with some noise edges on LRCK only.
+        assert_eq!(classify(1_000, 0), SampleRate::Unknown);
+    }
+}
@@ -1,3 +1,6 @@
+use core::sync::atomic::Ordering;
+
+use crate::browse::BROWSING;
 use crate::Command;
 use defmt::info;
 use embassy_rp::gpio::{Input, Pull};
@@ -17,10 +20,14 @@ pub async fn listen_rotary_encoder(
     mut encoder: PioEncoder<'static, PIO0, 0>,
 ) {
     loop {
-        match encoder.read().await {
-            Direction::Clockwise => control.send(Command::VolumeUp).await,
-            Direction::CounterClockwise => control.send(Command::VolumeDown).await,
+        let dir = encoder.read().await;
+        let cmd = match (BROWSING.load(Ordering::Relaxed), dir) {
+            (true, Direction::Clockwise) => Command::BrowseStep(1),
+            (true, Direction::CounterClockwise) => Command::BrowseStep(-1),
+            (false, Direction::Clockwise) => Command::VolumeUp,
+            (false, Direction::CounterClockwise) => Command::VolumeDown,
         };
+        control.send(cmd).await;
     }
 }
 
@@ -36,12 +43,20 @@ pub async fn listen_rotary_encoder_button(
         let start = Instant::now();
 
         match with_deadline(start + Duration::from_secs(5), btn.debounce()).await {
-            // Released before 5s: a short press (<1s) toggles play/pause;
-            // anything between 1s and 5s is treated as an aborted long press.
+            // Released before 5s: a short press (<1s) toggles play/pause, or
+            // selects the entry under the cursor while browsing; a longer
+            // one opens the queue, then the library, then goes back.
             Ok(_) => {
                 if start.elapsed() < Duration::from_secs(1) {
                     info!("Button short press");
-                    control.send(Command::TogglePlay).await;
+                    if BROWSING.load(Ordering::Relaxed) {
+                        control.send(Command::BrowseSelect).await;
+                    } else {
+                        control.send(Command::TogglePlay).await;
+                    }
+                } else {
+                    info!("Button held");
+                    control.send(Command::BrowseHold).await;
                 }
             }
             // Still held at 5s: toggle system power, then wait for release.
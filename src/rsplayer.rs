@@ -1,69 +1,326 @@
-use defmt::{debug, error};
+use core::cell::RefCell;
+
+// Plain defmt in this module: `host_log!` would queue on the very outbox
+// it implements.
+use defmt::{debug, error, warn};
 use embassy_rp::{peripherals::USB, usb::Driver};
+use embassy_sync::{
+    blocking_mutex::{raw::CriticalSectionRawMutex, Mutex},
+    signal::Signal,
+};
 use embassy_time::{with_timeout, Duration};
 use embassy_usb::class::cdc_acm::Sender;
-use rsplayer_wire::{FwPlayerCmd, FwToHost, MAX_FRAME};
+use heapless::Vec;
+use rsplayer_wire::{
+    caps, DacModel, DeviceState, DfuStatus, DisplayKind, FwPlayerCmd, FwToHost, Hello, IrProtocol,
+    LibraryQuery, ResetKind, ResetReason, MAX_FRAME, PROTOCOL_VERSION,
+};
 
-pub struct RsPlayer {
-    usb_sender: Sender<'static, Driver<'static, USB>>,
+use crate::dac::common::SampleRate;
+use crate::hid::{self, HidMode, MediaKey, MediaKeys};
+use crate::outbox::{self, Class, Pushed, Queued};
+
+const OUTBOX_LEN: usize = 32;
+
+/// Everything headed for the host, drained by [`usb_tx`].
+pub enum Outbound {
+    Msg(FwToHost),
+    /// Media key tap on the HID interface.
+    Key(MediaKey),
 }
 
-impl RsPlayer {
-    pub fn new(usb_sender: Sender<'static, Driver<'static, USB>>) -> Self {
-        RsPlayer { usb_sender }
-    }
+/// Bounded queue of outbound messages. Pushing never waits: the command
+/// loop must keep handling the knob while the host is slow or stalled.
+/// What makes room when it is full is up to [`outbox::push`]; queued volume
+/// and power reports are updated in place instead of queueing another.
+pub struct Outbox {
+    queue: Mutex<CriticalSectionRawMutex, RefCell<Vec<Outbound, OUTBOX_LEN>>>,
+    ready: Signal<CriticalSectionRawMutex, ()>,
+}
 
-    pub async fn send(&mut self, msg: &FwToHost) {
-        let mut buf = [0u8; MAX_FRAME];
-        let frame = match postcard::to_slice_cobs(msg, &mut buf) {
-            Ok(f) => f,
-            Err(_) => {
-                error!("postcard encode failed");
-                return;
+impl Queued for Outbound {
+    fn class(&self) -> Class {
+        match self {
+            Outbound::Msg(FwToHost::Log { .. }) => Class::Log,
+            Outbound::Msg(FwToHost::Power(_) | FwToHost::Ack { .. } | FwToHost::Nack { .. }) => {
+                Class::Kept
             }
-        };
+            _ => Class::Droppable,
+        }
+    }
 
-        if with_timeout(
-            Duration::from_millis(100),
-            self.usb_sender.wait_connection(),
-        )
-        .await
-        .is_err()
-        {
-            debug!("USB not connected (timeout), skipping");
-            return;
+    fn merge(&mut self, new: &Self) -> bool {
+        match (self, new) {
+            (Outbound::Msg(FwToHost::Volume(v)), Outbound::Msg(FwToHost::Volume(n))) => *v = *n,
+            (Outbound::Msg(FwToHost::Power(p)), Outbound::Msg(FwToHost::Power(n))) => *p = *n,
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl Outbox {
+    pub const fn new() -> Self {
+        Outbox {
+            queue: Mutex::new(RefCell::new(Vec::new())),
+            ready: Signal::new(),
+        }
+    }
+
+    /// Queues `msg`; `false` if it was dropped.
+    pub fn push(&self, msg: FwToHost) -> bool {
+        self.push_item(Outbound::Msg(msg))
+    }
+
+    fn push_item(&self, item: Outbound) -> bool {
+        let pushed = self.queue.lock(|q| outbox::push(&mut q.borrow_mut(), item));
+        match pushed {
+            Pushed::Evicted => warn!("Outbox full, dropped oldest message"),
+            Pushed::Refused => warn!("Outbox full, dropping message"),
+            Pushed::Queued | Pushed::Merged => {}
         }
+        self.ready.signal(());
+        pushed != Pushed::Refused
+    }
 
-        // Frame is at most MAX_FRAME bytes; chunk into max 64-byte USB packets.
-        for chunk in frame.chunks(64) {
-            match with_timeout(
-                Duration::from_millis(500),
-                self.usb_sender.write_packet(chunk),
-            )
-            .await
-            {
-                Ok(Ok(_)) => {}
-                Ok(Err(e)) => {
-                    error!("Failed to write packet: {}", e);
-                    return;
-                }
-                Err(_) => {
-                    error!("Write packet timed out");
-                    return;
-                }
+    async fn receive(&self) -> Outbound {
+        loop {
+            let next = self.queue.lock(|q| {
+                let mut q = q.borrow_mut();
+                (!q.is_empty()).then(|| q.remove(0))
+            });
+            if let Some(item) = next {
+                return item;
             }
+            self.ready.wait().await;
+        }
+    }
+}
+
+impl Default for Outbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Messages from code that does not own an `RsPlayer` — acks from the USB
+/// receive task, log lines from anywhere — share the queue.
+pub static HOST_OUTBOX: Outbox = Outbox::new();
+
+#[cfg(feature = "ak4490")]
+const DAC_MODEL: DacModel = DacModel::Ak4490;
+#[cfg(feature = "ak4497")]
+const DAC_MODEL: DacModel = DacModel::Ak4497;
+
+#[cfg(feature = "ili9488")]
+const DISPLAY_KIND: DisplayKind = DisplayKind::Ili9488;
+#[cfg(feature = "st7920")]
+const DISPLAY_KIND: DisplayKind = DisplayKind::St7920;
+
+const FEATURES: u32 = caps::INPUT_TABLE
+    | caps::RATE_METER
+    | caps::DAC_STATE
+    | caps::IR_FORWARD
+    | caps::DFU
+    | caps::HEARTBEAT
+    | caps::TRACK_EXT
+    | caps::POSITION
+    | SPDIF_CAPS
+    | DISPLAY_CAPS;
+
+#[cfg(feature = "wm8805")]
+const SPDIF_CAPS: u32 = caps::SPDIF_RECEIVER;
+#[cfg(not(feature = "wm8805"))]
+const SPDIF_CAPS: u32 = 0;
+
+/// Views only the ILI9488 layouts have.
+#[cfg(feature = "ili9488")]
+const DISPLAY_CAPS: u32 =
+    caps::ALBUM_ART | caps::QUEUE_BROWSER | caps::LIBRARY_BROWSER | caps::NOTIFY | caps::SPECTRUM;
+#[cfg(not(feature = "ili9488"))]
+const DISPLAY_CAPS: u32 = 0;
+
+/// Builds host messages and queues them on [`HOST_OUTBOX`]; none of its
+/// methods wait on USB.
+pub struct RsPlayer {
+    hid_mode: HidMode,
+}
+
+impl RsPlayer {
+    pub fn new(hid_mode: HidMode) -> Self {
+        hid::publish_mode(hid_mode);
+        RsPlayer { hid_mode }
+    }
+
+    pub fn hid_mode(&self) -> HidMode {
+        self.hid_mode
+    }
+
+    pub fn set_hid_mode(&mut self, mode: HidMode) {
+        hid::publish_mode(mode);
+        self.hid_mode = mode;
+    }
+
+    pub fn send(&mut self, msg: FwToHost) {
+        HOST_OUTBOX.push(msg);
+    }
+
+    /// Firmware side of the handshake: protocol and firmware version plus
+    /// what this build can do, so the host can hide what is not there.
+    pub fn send_hello(&mut self) {
+        self.send(FwToHost::Hello(Hello {
+            protocol: PROTOCOL_VERSION,
+            firmware: env!("CARGO_PKG_VERSION").try_into().unwrap_or_default(),
+            dac: DAC_MODEL,
+            display: DISPLAY_KIND,
+            features: FEATURES,
+        }));
+    }
+
+    pub fn send_reset_reason(&mut self, reason: ResetReason) {
+        self.send(FwToHost::ResetReason(reason));
+    }
+
+    pub fn send_state(&mut self, state: DeviceState) {
+        self.send(FwToHost::State(state));
+    }
+
+    pub fn send_ir(&mut self, address: u8, command: u8, repeat: bool) {
+        self.send(FwToHost::Ir {
+            protocol: IrProtocol::Nec,
+            address,
+            command,
+            repeat,
+        });
+    }
+
+    /// Asks for the current cover, e.g. after the art layout was entered
+    /// and the panel has nothing to show.
+    pub fn send_art_request(&mut self) {
+        self.send(FwToHost::ArtRequest);
+    }
+
+    /// Asks for the queue page starting at `offset`, or with `None` for
+    /// the page holding the current track.
+    pub fn send_queue_request(&mut self, offset: Option<u32>) {
+        self.send(FwToHost::QueueRequest { offset });
+    }
+
+    pub fn send_library_request(&mut self, query: LibraryQuery) {
+        self.send(FwToHost::LibraryRequest(query));
+    }
+
+    pub fn send_dfu_status(&mut self, status: DfuStatus) {
+        self.send(FwToHost::Dfu(status));
+    }
+
+    /// Firmware liveness; sent every few seconds whether or not the host
+    /// sends heartbeats of its own.
+    pub fn send_heartbeat(&mut self, uptime_ms: u32) {
+        self.send(FwToHost::Heartbeat { uptime_ms });
+    }
+
+    pub fn send_reset_token(&mut self, kind: ResetKind, token: u32) {
+        self.send(FwToHost::ResetToken { kind, token });
+    }
+
+    /// Player commands go out as rsplayer messages, media keys or both,
+    /// depending on the HID mode.
+    pub fn send_player(&mut self, cmd: FwPlayerCmd) {
+        if self.hid_mode.hid() {
+            HOST_OUTBOX.push_item(Outbound::Key(MediaKey::Player(cmd.clone())));
         }
+        if self.hid_mode.rsplayer() {
+            self.send(FwToHost::Player(cmd));
+        }
+    }
+
+    /// Volume and mute keys for the host mixer (`HidMode::HidVolume`).
+    pub fn send_volume_key(&mut self, key: MediaKey) {
+        HOST_OUTBOX.push_item(Outbound::Key(key));
+    }
+
+    pub fn send_current_volume(&mut self, vol: u8) {
+        self.send(FwToHost::Volume(vol));
+    }
+
+    pub fn send_power_state(&mut self, is_on: bool) {
+        self.send(FwToHost::Power(is_on));
+    }
+
+    /// What the DAC is actually being fed; `Unknown` reports as rate 0.
+    pub fn send_format(&mut self, rate: SampleRate) {
+        self.send(FwToHost::Format {
+            dsd: rate.is_dsd(),
+            rate_hz: rate.hz(),
+        });
     }
 
-    pub async fn send_player(&mut self, cmd: FwPlayerCmd) {
-        self.send(&FwToHost::Player(cmd)).await;
+    pub fn send_input(&mut self, index: u8, name: &str) {
+        self.send(FwToHost::Input {
+            index,
+            name: name.try_into().unwrap_or_default(),
+        });
     }
 
-    pub async fn send_current_volume(&mut self, vol: u8) {
-        self.send(&FwToHost::Volume(vol)).await;
+    pub fn send_filter(&mut self, filter: u8) {
+        self.send(FwToHost::Filter(filter));
     }
 
-    pub async fn send_power_state(&mut self, is_on: bool) {
-        self.send(&FwToHost::Power(is_on)).await;
+    pub fn send_sound_setting(&mut self, setting: u8) {
+        self.send(FwToHost::SoundSetting(setting));
+    }
+
+    pub fn send_dac_fault(&mut self, faulted: bool) {
+        self.send(FwToHost::DacFault(faulted));
+    }
+}
+
+/// Sole writer of the CDC and HID endpoints. Slow or missing USB only
+/// backs up [`HOST_OUTBOX`], never the tasks feeding it.
+#[embassy_executor::task]
+pub async fn usb_tx(
+    mut usb_sender: Sender<'static, Driver<'static, USB>>,
+    mut media_keys: MediaKeys,
+) {
+    loop {
+        match HOST_OUTBOX.receive().await {
+            Outbound::Msg(msg) => write(&mut usb_sender, &msg).await,
+            Outbound::Key(key) => media_keys.tap(&key).await,
+        }
+    }
+}
+
+async fn write(usb_sender: &mut Sender<'static, Driver<'static, USB>>, msg: &FwToHost) {
+    let mut buf = [0u8; MAX_FRAME];
+    let frame = match postcard::to_slice_cobs(msg, &mut buf) {
+        Ok(f) => f,
+        Err(_) => {
+            error!("postcard encode failed");
+            return;
+        }
+    };
+
+    if with_timeout(Duration::from_millis(100), usb_sender.wait_connection())
+        .await
+        .is_err()
+    {
+        debug!("USB not connected (timeout), skipping");
+        return;
+    }
+
+    // Frame is at most MAX_FRAME bytes; chunk into max 64-byte USB packets.
+    for chunk in frame.chunks(64) {
+        match with_timeout(Duration::from_millis(500), usb_sender.write_packet(chunk)).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => {
+                error!("Failed to write packet: {}", e);
+                return;
+            }
+            Err(_) => {
+                error!("Write packet timed out");
+                return;
+            }
+        }
     }
 }
@@ -0,0 +1,138 @@
+/// A setting with values `0..count` that can both be cycled locally (IR,
+/// encoder) and set to a value by the host. Both paths resolve through
+/// [`Setting::resolve`] and then share one apply-and-persist step, so the
+/// host can set exactly the values cycling walks through.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Setting {
+    pub count: u8,
+}
+
+/// How a setting is being changed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
+pub enum Change {
+    /// Local cycling: one step on, wrapping to 0.
+    Next,
+    /// A value sent by the host.
+    Set(u8),
+}
+
+impl Setting {
+    pub fn accepts(self, val: u8) -> bool {
+        val < self.count
+    }
+
+    /// The value to apply and persist for `change`, `None` when a host
+    /// value is out of range. Cycling from a value that is itself out of
+    /// range (stored by an older firmware) restarts at 0.
+    pub fn resolve(self, current: u8, change: Change) -> Option<u8> {
+        match change {
+            Change::Set(val) => self.accepts(val).then_some(val),
+            Change::Next if self.accepts(current.saturating_add(1)) => Some(current + 1),
+            Change::Next => Some(0),
+        }
+    }
+
+    /// `val` if in range, otherwise the default 0.
+    pub fn or_default(self, val: u8) -> u8 {
+        if self.accepts(val) {
+            val
+        } else {
+            0
+        }
+    }
+}
+
+/// Sharp, slow, short delay sharp, short delay slow (`FilterType::from`).
+/// Sound settings 0..=2 plus the default arm of `change_sound_setting`.
+pub mod ak4490 {
+    use super::Setting;
+
+    pub const FILTER: Setting = Setting { count: 4 };
+    pub const SOUND_SETTING: Setting = Setting { count: 4 };
+}
+
+/// The AK4490 filters plus super slow. Sound settings 1..=4 plus the
+/// default arm of `change_sound_setting`, reached as 0.
+pub mod ak4497 {
+    use super::Setting;
+
+    pub const FILTER: Setting = Setting { count: 5 };
+    pub const SOUND_SETTING: Setting = Setting { count: 5 };
+}
+
+/// Normal, VU meter, big info, album art, spectrum (`DisplayMode::from`).
+pub const DISPLAY_MODE: Setting = Setting { count: 5 };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [(&str, Setting); 5] = [
+        ("ak4490 filter", ak4490::FILTER),
+        ("ak4490 sound", ak4490::SOUND_SETTING),
+        ("ak4497 filter", ak4497::FILTER),
+        ("ak4497 sound", ak4497::SOUND_SETTING),
+        ("display mode", DISPLAY_MODE),
+    ];
+
+    /// Values local cycling visits, starting from the power-on default.
+    fn cycle(setting: Setting) -> [bool; 256] {
+        let mut seen = [false; 256];
+        let mut val = 0;
+        for _ in 0..256 {
+            seen[val as usize] = true;
+            val = setting.resolve(val, Change::Next).unwrap();
+        }
+        seen
+    }
+
+    #[test]
+    fn set_accepts_exactly_what_cycling_reaches() {
+        for (name, setting) in ALL {
+            let reached = cycle(setting);
+            for val in 0..=u8::MAX {
+                let set = setting.resolve(0, Change::Set(val));
+                assert_eq!(set.is_some(), reached[val as usize], "{} {}", name, val);
+                assert_eq!(set.is_some(), setting.accepts(val), "{} {}", name, val);
+            }
+        }
+    }
+
+    #[test]
+    fn set_and_next_resolve_to_the_same_value() {
+        for (name, setting) in ALL {
+            for val in 0..setting.count {
+                let prev = (val + setting.count - 1) % setting.count;
+                let next = setting.resolve(prev, Change::Next);
+                // Whatever the current value, a host set lands where the
+                // cycle would, and nothing else differs downstream.
+                for current in [0, prev, val, u8::MAX] {
+                    assert_eq!(
+                        setting.resolve(current, Change::Set(val)),
+                        next,
+                        "{} {}",
+                        name,
+                        val
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cycle_wraps_and_recovers_from_stale_values() {
+        for (name, setting) in ALL {
+            let last = setting.count - 1;
+            assert_eq!(setting.resolve(last, Change::Next), Some(0), "{}", name);
+            assert_eq!(
+                setting.resolve(setting.count, Change::Next),
+                Some(0),
+                "{}",
+                name
+            );
+            assert_eq!(setting.resolve(u8::MAX, Change::Next), Some(0), "{}", name);
+            assert_eq!(setting.or_default(last), last, "{}", name);
+            assert_eq!(setting.or_default(setting.count), 0, "{}", name);
+        }
+    }
+}
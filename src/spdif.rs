@@ -0,0 +1,213 @@
+use defmt::debug;
+use embassy_futures::select::select;
+use embassy_rp::gpio::{Input, Pull};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Sender};
+use embassy_time::Timer;
+
+use crate::dac::common::SampleRate;
+use crate::hostlog::host_log;
+use crate::i2c_helper::{I2CHelper, I2cBus};
+use crate::{Command, RateSource, SpdifPins, POWER_ON};
+
+/// WM8805 with CSB/GPO2 pulled low.
+const ADDR_WM8805: u8 = 0x3A;
+
+const REG_RST_DEVID1: u8 = 0x00;
+const REG_DEVID2: u8 = 0x01;
+const REG_INTMASK: u8 = 0x0A;
+const REG_INTSTAT: u8 = 0x0B;
+const REG_SPDSTAT: u8 = 0x0C;
+/// Channel status byte 3 — bits 3:0 carry the sample frequency.
+const REG_RXCHAN4: u8 = 0x10;
+const REG_AIFRX: u8 = 0x1C;
+const REG_PWRDN: u8 = 0x1E;
+
+/// DEVID2:DEVID1 read back as 0x88, 0x05.
+const DEVICE_ID: u16 = 0x8805;
+
+// INTSTAT bits (cleared on read)
+const INT_INVALID: u8 = 1 << 1;
+const INT_TRANS_ERR: u8 = 1 << 3;
+
+// SPDSTAT bits
+const SPD_AUDIO_N: u8 = 1 << 0;
+const SPD_CPY_N: u8 = 1 << 2;
+const SPD_DEEMPH: u8 = 1 << 3;
+const SPD_UNLOCK: u8 = 1 << 6;
+
+/// What the S/PDIF receiver currently sees on its input.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, defmt::Format)]
+pub struct SpdifStatus {
+    pub locked: bool,
+    pub rate: SampleRate,
+    /// Stream flagged as non-PCM (AC-3, DTS, ...) — must not reach the DAC.
+    pub non_audio: bool,
+    /// 50/15 µs pre-emphasis flagged in the channel status.
+    pub emphasis: bool,
+    /// Copyright not asserted in the channel status.
+    pub copy_permitted: bool,
+    /// Invalid-frame and bi-phase/parity errors seen since power-on.
+    pub errors: u16,
+}
+
+impl SpdifStatus {
+    const UNLOCKED: SpdifStatus = SpdifStatus {
+        locked: false,
+        rate: SampleRate::Unknown,
+        non_audio: false,
+        emphasis: false,
+        copy_permitted: false,
+        errors: 0,
+    };
+
+    /// Rate the DAC should be switched to, `Unknown` while there is nothing
+    /// playable.
+    pub fn playable_rate(&self) -> SampleRate {
+        if self.locked && !self.non_audio {
+            self.rate
+        } else {
+            SampleRate::Unknown
+        }
+    }
+}
+
+/// Maps the receiver's coarse frequency detector (SPDSTAT[5:4]) and the
+/// channel status sample frequency field to a `SampleRate`. The channel
+/// status is exact but optional in consumer streams, so it is only trusted
+/// when it agrees with the measured family.
+fn rate_from_status(rec_freq: u8, cs_freq: u8) -> SampleRate {
+    let from_cs = match cs_freq & 0x0F {
+        0b0000 => SampleRate::Pcm441,
+        0b0010 => SampleRate::Pcm48,
+        0b0011 => SampleRate::Pcm32,
+        0b1000 => SampleRate::Pcm882,
+        0b1010 => SampleRate::Pcm96,
+        0b1100 => SampleRate::Pcm1764,
+        0b1110 => SampleRate::Pcm192,
+        _ => SampleRate::Unknown,
+    };
+    match (rec_freq & 0b11, from_cs) {
+        (0b00, r @ (SampleRate::Pcm1764 | SampleRate::Pcm192)) => r,
+        (0b00, _) => SampleRate::Pcm192,
+        (0b01, r @ (SampleRate::Pcm882 | SampleRate::Pcm96)) => r,
+        (0b01, _) => SampleRate::Pcm96,
+        (0b10, r @ (SampleRate::Pcm441 | SampleRate::Pcm48)) => r,
+        (0b10, _) => SampleRate::Pcm48,
+        (0b11, _) => SampleRate::Pcm32,
+        _ => SampleRate::Unknown,
+    }
+}
+
+pub struct Wm8805 {
+    i2c_helper: I2CHelper,
+    int_pin: Input<'static>,
+    errors: u16,
+}
+
+impl Wm8805 {
+    pub fn new(bus: &'static I2cBus, pins: SpdifPins) -> Self {
+        Wm8805 {
+            i2c_helper: I2CHelper::new(bus, ADDR_WM8805),
+            int_pin: Input::new(pins.pin26_int, Pull::Up),
+            errors: 0,
+        }
+    }
+
+    /// Resets and configures the receiver: S/PDIF RX, PLL and AIF powered,
+    /// TX off, AIF as I2S master with 24-bit words, all interrupts unmasked
+    /// on GPO0 (INT_N). Returns false if the chip does not answer.
+    pub async fn initialize(&mut self) -> bool {
+        self.errors = 0;
+        if self.i2c_helper.try_write_register(REG_RST_DEVID1, 0).await.is_none() {
+            return false;
+        }
+        Timer::after_millis(10).await;
+        let id = match (
+            self.i2c_helper.try_read_register(REG_RST_DEVID1).await,
+            self.i2c_helper.try_read_register(REG_DEVID2).await,
+        ) {
+            (Some(lo), Some(hi)) => u16::from_be_bytes([hi, lo]),
+            _ => return false,
+        };
+        if id != DEVICE_ID {
+            host_log!(Warn, "Unexpected S/PDIF receiver id: {:x}", id);
+            return false;
+        }
+        self.i2c_helper.try_write_register(REG_AIFRX, 0b0100_1010).await;
+        self.i2c_helper.try_write_register(REG_INTMASK, 0x00).await;
+        self.i2c_helper.try_write_register(REG_PWRDN, 0b0000_0100).await;
+        host_log!(Info, "WM8805 initialized");
+        true
+    }
+
+    pub async fn read_status(&mut self) -> Option<SpdifStatus> {
+        let int = self.i2c_helper.try_read_register(REG_INTSTAT).await?;
+        let spd = self.i2c_helper.try_read_register(REG_SPDSTAT).await?;
+        let cs = self.i2c_helper.try_read_register(REG_RXCHAN4).await?;
+        if int & (INT_INVALID | INT_TRANS_ERR) != 0 {
+            self.errors = self.errors.saturating_add(1);
+        }
+        let locked = spd & SPD_UNLOCK == 0;
+        if !locked {
+            return Some(SpdifStatus {
+                errors: self.errors,
+                ..SpdifStatus::UNLOCKED
+            });
+        }
+        Some(SpdifStatus {
+            locked,
+            rate: rate_from_status(spd >> 4, cs),
+            non_audio: spd & SPD_AUDIO_N != 0,
+            emphasis: spd & SPD_DEEMPH != 0,
+            copy_permitted: spd & SPD_CPY_N != 0,
+            errors: self.errors,
+        })
+    }
+}
+
+#[embassy_executor::task]
+pub async fn listen_spdif_receiver(
+    control: Sender<'static, CriticalSectionRawMutex, Command, 64>,
+    mut receiver: Wm8805,
+) {
+    let mut initialized = false;
+    let mut last: Option<SpdifStatus> = None;
+    loop {
+        // The receiver sits behind the PSU relay like the DAC.
+        if !POWER_ON.load(core::sync::atomic::Ordering::Relaxed) {
+            initialized = false;
+            last = None;
+            Timer::after_millis(500).await;
+            continue;
+        }
+        if !initialized {
+            initialized = receiver.initialize().await;
+            if !initialized {
+                Timer::after_secs(2).await;
+                continue;
+            }
+        }
+
+        // INT_N flags lock and channel status changes; the poll catches
+        // error counts and a missed edge.
+        select(receiver.int_pin.wait_for_falling_edge(), Timer::after_millis(500)).await;
+
+        let Some(status) = receiver.read_status().await else {
+            host_log!(Warn, "S/PDIF receiver not responding");
+            initialized = false;
+            continue;
+        };
+        if last == Some(status) {
+            continue;
+        }
+        debug!("S/PDIF status: {}", status);
+        let rate_changed = last.map(|l| l.playable_rate()) != Some(status.playable_rate());
+        last = Some(status);
+        control.send(Command::UpdateSpdifStatus(status)).await;
+        if rate_changed {
+            control
+                .send(Command::UpdateSampleRate(RateSource::Spdif, status.playable_rate()))
+                .await;
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use embassy_rp::watchdog::{ResetReason as WatchdogReason, Watchdog};
+use embassy_time::{Duration, Instant, Timer};
+use rsplayer_wire::ResetReason;
+
+use crate::hostlog::host_log;
+
+/// Tasks whose progress keeps the watchdog fed. Each must call
+/// [`check_in`] more often than its deadline.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, defmt::Format)]
+pub enum Task {
+    /// `process_commands`; its loop wakes at least every 5s.
+    Commands = 0,
+    /// `tick_display`, every 50ms plus the draw.
+    Display = 1,
+    /// `usb_task`, driving the USB device.
+    Usb = 2,
+    /// The rate meter gate loop, standing in for the core1 executor.
+    Core1 = 3,
+}
+
+const TASKS: [(Task, u32); 4] = [
+    (Task::Commands, 10_000),
+    (Task::Display, 3_000),
+    (Task::Usb, 3_000),
+    (Task::Core1, 3_000),
+];
+
+/// Uptime in ms of each task's last check-in.
+static CHECK_INS: [AtomicU32; 4] = [const { AtomicU32::new(0) }; 4];
+
+/// Scratch register 0 carries the reason of an intentional reset into the
+/// next boot. The boot ROM only uses registers 4-7.
+const REASON_SCRATCH: usize = 0;
+/// Upper half marks the scratch value as ours; bits 8-15 hold the stalled
+/// task for watchdog resets, bits 0-7 the reason.
+const REASON_MAGIC: u32 = 0x5253_0000;
+
+const REASON_WATCHDOG: u8 = 1;
+const REASON_PANIC: u8 = 2;
+const REASON_HOST: u8 = 3;
+const REASON_UPDATE: u8 = 4;
+
+pub fn check_in(task: Task) {
+    CHECK_INS[task as usize].store(Instant::now().as_millis() as u32, Ordering::Relaxed);
+}
+
+fn record(reason: u8, task: u8) {
+    embassy_rp::pac::WATCHDOG
+        .scratch(REASON_SCRATCH)
+        .write_value(REASON_MAGIC | (task as u32) << 8 | reason as u32);
+}
+
+/// Stores why the firmware is about to reset itself, to be reported after
+/// the reboot.
+pub fn record_reset(reason: ResetReason) {
+    let code = match reason {
+        ResetReason::Host => REASON_HOST,
+        ResetReason::Update => REASON_UPDATE,
+        _ => return,
+    };
+    record(code, 0);
+}
+
+/// Shown on the standby screen after an unexpected or remote reset.
+pub fn describe(reason: ResetReason) -> &'static str {
+    match reason {
+        ResetReason::PowerOn => "",
+        ResetReason::Watchdog => "Reset by watchdog",
+        ResetReason::Panic => "Reset after crash",
+        ResetReason::Host => "Reset by host",
+        ResetReason::Update => "Firmware updated",
+    }
+}
+
+/// Why this boot happened, read once at startup. A watchdog timeout nobody
+/// recorded (e.g. a hang inside the supervisor itself) still reports as
+/// `Watchdog`.
+pub fn take_reset_reason(watchdog: &Watchdog) -> ResetReason {
+    let scratch = embassy_rp::pac::WATCHDOG.scratch(REASON_SCRATCH);
+    let val = scratch.read();
+    scratch.write_value(0);
+    let recorded = (val & 0xFFFF_0000 == REASON_MAGIC).then_some((val & 0xFF) as u8);
+    let reason = match (recorded, watchdog.reset_reason()) {
+        (Some(REASON_HOST), _) => ResetReason::Host,
+        (Some(REASON_UPDATE), _) => ResetReason::Update,
+        (Some(REASON_PANIC), _) => ResetReason::Panic,
+        (_, Some(WatchdogReason::TimedOut)) => ResetReason::Watchdog,
+        _ => ResetReason::PowerOn,
+    };
+    if recorded == Some(REASON_WATCHDOG) {
+        host_log!(
+            Error,
+            "Last reset: watchdog, task {} stalled",
+            (val >> 8) & 0xFF
+        );
+    } else {
+        host_log!(Info, "Last reset: {:?}", reason);
+    }
+    reason
+}
+
+/// Feeds the watchdog only while every critical task keeps checking in.
+/// Once one misses its deadline the supervisor records it and lets the
+/// watchdog bite. The bootloader has already started the watchdog, so this
+/// task has to run from the beginning.
+#[embassy_executor::task]
+pub async fn supervise(mut watchdog: Watchdog) {
+    // A halted core stops the check-ins; without this every breakpoint
+    // ends in a reset.
+    watchdog.pause_on_debug(true);
+    watchdog.start(Duration::from_secs(8));
+    loop {
+        let now = Instant::now().as_millis() as u32;
+        let stalled = TASKS.iter().find(|(task, deadline)| {
+            now.wrapping_sub(CHECK_INS[*task as usize].load(Ordering::Relaxed)) > *deadline
+        });
+        if let Some((task, _)) = stalled {
+            host_log!(Error, "Task {:?} stalled, letting the watchdog reset", task);
+            record(REASON_WATCHDOG, *task as u8);
+            return;
+        }
+        watchdog.feed();
+        Timer::after_secs(1).await;
+    }
+}
+
+/// Release builds record the panic before resetting. Debug builds keep
+/// `panic-probe` so the panic shows up on the probe.
+#[cfg(feature = "release")]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    record(REASON_PANIC, 0);
+    cortex_m::peripheral::SCB::sys_reset()
+}
@@ -1,10 +1,21 @@
-use defmt::{error, info, warn};
+use defmt::debug;
 use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Sender};
 
-use crate::Command;
+use crate::dac;
+use crate::frame::FrameDecoder;
+use crate::hostlog::host_log;
+use crate::input::{input_name, is_permutation, InputMask, InputPolicy};
+use crate::ir;
+use crate::rsplayer::HOST_OUTBOX;
+use crate::setting::DISPLAY_MODE;
+use crate::{Bulk, Command, BULK_SLOT, POWER_ON};
 
-use heapless::Vec;
-use rsplayer_wire::{HostToFw, MAX_FRAME};
+use rsplayer_wire::{FwToHost, HostFrame, HostToFw, NackReason, MAX_FRAME, PROTOCOL_VERSION};
+
+/// Same major version (high byte) — minor bumps only add messages.
+fn protocol_compatible(host: u16) -> bool {
+    host >> 8 == PROTOCOL_VERSION >> 8
+}
 
 #[embassy_executor::task]
 pub async fn listen_usb_commands(
@@ -15,58 +26,148 @@ pub async fn listen_usb_commands(
     >,
 ) {
     let mut buf = [0u8; 64];
-    let mut frame: Vec<u8, MAX_FRAME> = Vec::new();
+    let mut decoder: FrameDecoder<HostFrame, MAX_FRAME> = FrameDecoder::new();
 
     loop {
         usb_rx.wait_connection().await;
-        info!("Connected");
+        host_log!(Info, "Connected");
         control.send(Command::UsbConnected).await;
-        frame.clear();
+        decoder.reset();
+        // Hosts predating the handshake never send a hello; keep serving
+        // them until one announces an incompatible protocol.
+        let mut compatible = true;
         loop {
-            match usb_rx.read_packet(&mut buf).await {
+            let n = match usb_rx.read_packet(&mut buf).await {
                 Ok(0) => {
-                    info!("Disconnected");
+                    host_log!(Info, "Disconnected");
                     break;
                 }
-                Ok(n) => {
-                    for &byte in &buf[..n] {
-                        if byte == 0x00 {
-                            // End of COBS frame: decode in place.
-                            if !frame.is_empty() {
-                                match postcard::from_bytes_cobs::<HostToFw>(&mut frame) {
-                                    Ok(msg) => {
-                                        if let Some(cmd) = host_to_fw_to_command(msg) {
-                                            control.send(cmd).await;
-                                        }
-                                    }
-                                    Err(_) => {
-                                        warn!("Failed to decode HostToFw frame");
-                                    }
-                                }
-                            }
-                            frame.clear();
-                        } else if frame.push(byte).is_err() {
-                            // Frame longer than MAX_FRAME — resync at the next 0x00.
-                            warn!("USB frame overflow, dropping");
-                            frame.clear();
-                        }
-                    }
-                }
+                Ok(n) => n,
                 Err(e) => {
-                    error!("USB read error: {:?}", e);
+                    host_log!(Error, "USB read error: {:?}", e);
                     break;
                 }
+            };
+            for res in decoder.feed(&buf[..n]) {
+                match res {
+                    Ok(HostFrame {
+                        msg: HostToFw::Hello { protocol },
+                        seq,
+                    }) => {
+                        compatible = protocol_compatible(protocol);
+                        if !compatible {
+                            host_log!(
+                                Warn,
+                                "Host protocol {:x} incompatible with {:x}",
+                                protocol,
+                                PROTOCOL_VERSION
+                            );
+                        }
+                        control.send(Command::HostHello { compatible }).await;
+                        reply(seq, Ok(()));
+                    }
+                    Ok(HostFrame { seq, msg }) if compatible => {
+                        let res = match host_to_fw_to_command(msg) {
+                            Ok(Inbound::Command(cmd)) => dispatch(&control, seq, cmd).await,
+                            Ok(Inbound::Bulk(bulk)) => dispatch_bulk(&control, seq, bulk).await,
+                            Err(reason) => Err(reason),
+                        };
+                        reply(seq, res);
+                    }
+                    Ok(HostFrame { seq, .. }) => {
+                        debug!("Ignoring command from incompatible host");
+                        reply(seq, Err(NackReason::Unsupported));
+                    }
+                    // No sequence number to nack; the host retries once its
+                    // ack times out.
+                    Err(e) => host_log!(Warn, "Dropping USB frame: {:?}", e),
+                }
             }
         }
+        debug!("USB frame stats: {}", decoder.stats());
+        ir::release_host();
+    }
+}
+
+/// Queues a host command. Unsequenced (streaming) messages wait for room
+/// like before; sequenced ones are refused instead, so the host learns about
+/// a full queue and can retry rather than stalling the receive loop.
+async fn dispatch(
+    control: &Sender<'static, CriticalSectionRawMutex, Command, 64>,
+    seq: u16,
+    cmd: Command,
+) -> Result<(), NackReason> {
+    if !cmd.allowed_while_off() && !POWER_ON.load(core::sync::atomic::Ordering::Relaxed) {
+        return Err(NackReason::PowerOff);
+    }
+    if seq == 0 {
+        control.send(cmd).await;
+        return Ok(());
+    }
+    control.try_send(cmd).map_err(|_| NackReason::Busy)
+}
+
+/// Parks a large payload in `BULK_SLOT` and queues the `Command::Bulk` that
+/// hands it to the command loop, with the same wait-or-refuse split as
+/// [`dispatch`].
+async fn dispatch_bulk(
+    control: &Sender<'static, CriticalSectionRawMutex, Command, 64>,
+    seq: u16,
+    bulk: Bulk,
+) -> Result<(), NackReason> {
+    if !bulk.allowed_while_off() && !POWER_ON.load(core::sync::atomic::Ordering::Relaxed) {
+        return Err(NackReason::PowerOff);
+    }
+    if seq == 0 {
+        BULK_SLOT.send(bulk).await;
+        control.send(Command::Bulk).await;
+        return Ok(());
+    }
+    BULK_SLOT.try_send(bulk).map_err(|_| NackReason::Busy)?;
+    if control.try_send(Command::Bulk).is_err() {
+        // The slot was empty and only this task fills it, so what is
+        // parked now is this payload.
+        BULK_SLOT.try_receive().ok();
+        return Err(NackReason::Busy);
     }
+    Ok(())
 }
 
-fn host_to_fw_to_command(msg: HostToFw) -> Option<Command> {
-    Some(match msg {
+/// Sequence 0 marks fire-and-forget messages (VU, progress, ...), which get
+/// no reply.
+fn reply(seq: u16, res: Result<(), NackReason>) {
+    if seq == 0 {
+        return;
+    }
+    let msg = match res {
+        Ok(()) => FwToHost::Ack { seq },
+        Err(reason) => {
+            debug!("Nack {}: {}", seq, reason);
+            FwToHost::Nack { seq, reason }
+        }
+    };
+    if !HOST_OUTBOX.push(msg) {
+        host_log!(Warn, "Reply queue full, dropping reply to {}", seq);
+    }
+}
+
+/// A host message as queued for the command loop.
+enum Inbound {
+    Command(Command),
+    Bulk(Bulk),
+}
+
+/// Refuses what the command loop would ignore — unknown or disabled
+/// inputs, edits it rejects, settings out of range — so an ACK means the
+/// command takes effect.
+fn host_to_fw_to_command(msg: HostToFw) -> Result<Inbound, NackReason> {
+    let inputs = InputMask::published();
+    let cmd = match msg {
         HostToFw::SetVolume(v) => Command::SetVolume(v),
         HostToFw::VolumeUp => Command::VolumeUp,
         HostToFw::VolumeDown => Command::VolumeDown,
         HostToFw::QueryVolume => Command::QueryCurrentVolume,
+        HostToFw::QueryState => Command::QueryState,
         HostToFw::PowerOn => Command::PowerOn,
         HostToFw::PowerOff => Command::PowerOff,
         HostToFw::Track { title, artist, album } => {
@@ -75,7 +176,119 @@ fn host_to_fw_to_command(msg: HostToFw) -> Option<Command> {
         HostToFw::Progress { current, total, percent } => {
             Command::UpdateProgress { current, total, percent }
         }
+        HostToFw::TrackExt(ext) => Command::UpdateTrackExt(ext),
+        HostToFw::UpNext { title, artist } => Command::UpdateUpNext { title, artist },
+        HostToFw::Position { position_ms, duration_ms, playing } => {
+            Command::UpdatePosition { position_ms, duration_ms, playing }
+        }
         HostToFw::Vu { left, right } => Command::UpdateVU { left, right },
+        HostToFw::Spectrum(bands) => Command::UpdateSpectrum(bands),
         HostToFw::PlaybackMode(mode) => Command::UpdatePlaybackMode(mode),
-    })
+        HostToFw::SetInputName { index, name } if inputs.contains(index) && !name.is_empty() => {
+            Command::SetInputName {
+                index,
+                name: input_name(&name),
+            }
+        }
+        HostToFw::SetInputEnabled { index, enabled } if inputs.can_set_enabled(index, enabled) => {
+            Command::SetInputEnabled { index, enabled }
+        }
+        HostToFw::SetInputOrder(order) if is_permutation(&order, inputs.len()) => {
+            let mut ids = heapless::Vec::new();
+            ids.extend_from_slice(&order).ok();
+            Command::SetInputOrder(ids)
+        }
+        HostToFw::SetInputPolicy(policy) => match InputPolicy::from(policy) {
+            policy if policy.supported() => Command::SetInputPolicy(policy),
+            _ => return Err(NackReason::Unsupported),
+        },
+        HostToFw::SetFilter(val) if dac::FILTER.accepts(val) => Command::SetDacFilterType(val),
+        HostToFw::SetSoundSetting(val) if dac::SOUND_SETTING.accepts(val) => {
+            Command::SetDacSoundSetting(val)
+        }
+        HostToFw::SelectInput(index) if inputs.is_enabled(index) => Command::SelectInput(index),
+        HostToFw::SetDisplayMode(mode) if DISPLAY_MODE.accepts(mode) => {
+            Command::SetDisplayMode(mode)
+        }
+        HostToFw::IrLearn(on) => Command::SetIrLearn(on),
+        HostToFw::IrForward {
+            address,
+            command,
+            forward,
+        } => Command::SetIrForward {
+            address,
+            command,
+            forward,
+        },
+        HostToFw::IrForwardClear => Command::ClearIrForward,
+        HostToFw::DfuBegin { size, crc32 } => Command::DfuBegin { size, crc32 },
+        HostToFw::DfuChunk { offset, data } => {
+            return Ok(Inbound::Bulk(Bulk::DfuChunk { offset, data }));
+        }
+        HostToFw::DfuFinish => Command::DfuFinish,
+        HostToFw::DfuAbort => Command::DfuAbort,
+        HostToFw::RequestReset(kind) => Command::RequestReset(kind),
+        HostToFw::ConfirmReset { kind, token } => Command::ConfirmReset { kind, token },
+        HostToFw::SetHidMode(mode) => Command::SetHidMode(mode.into()),
+        HostToFw::SetLogLevel(level) => Command::SetLogLevel(level),
+        HostToFw::Heartbeat => Command::HostHeartbeat,
+        HostToFw::ArtBegin {
+            id,
+            width,
+            height,
+            format,
+        } => Command::ArtBegin {
+            id,
+            width,
+            height,
+            format,
+        },
+        HostToFw::ArtChunk { id, offset, data } => {
+            return Ok(Inbound::Bulk(Bulk::ArtChunk { id, offset, data }));
+        }
+        HostToFw::ArtCancel => Command::ArtCancel,
+        HostToFw::SetHostRecovery(secs) => Command::SetHostRecovery(secs),
+        HostToFw::QueuePage {
+            offset,
+            total,
+            current,
+            items,
+        } => {
+            return Ok(Inbound::Bulk(Bulk::UpdateQueuePage {
+                offset,
+                total,
+                current,
+                items,
+            }));
+        }
+        HostToFw::LibraryPage {
+            query,
+            total,
+            items,
+        } => {
+            return Ok(Inbound::Bulk(Bulk::UpdateLibraryPage {
+                query,
+                total,
+                items,
+            }));
+        }
+        HostToFw::Notify {
+            text,
+            severity,
+            duration_ms,
+        } => Command::Notify {
+            text,
+            severity,
+            duration_ms,
+        },
+        HostToFw::Hello { .. }
+        | HostToFw::SetInputName { .. }
+        | HostToFw::SetInputEnabled { .. }
+        | HostToFw::SetInputOrder(_)
+        | HostToFw::SelectInput(_)
+        | HostToFw::SetFilter(_)
+        | HostToFw::SetSoundSetting(_)
+        | HostToFw::SetDisplayMode(_) => return Err(NackReason::Unsupported),
+    };
+    Ok(Inbound::Command(cmd))
 }